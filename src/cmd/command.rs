@@ -1,9 +1,19 @@
 //! Command implementation and execution logic.
 
-use crate::cmd::{error::Error, types::*};
+use crate::cmd::{
+    error::Error,
+    pipeline::{DEFAULT_BUFFER_SIZE, collect_stream},
+    record,
+    status::Status,
+    types::*,
+    xargs::Xargs,
+};
 use std::ffi::OsStr;
 use std::io::{Read, Write};
 use std::path::Path;
+use std::process::{ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 impl Cmd {
     /// Create a new command.
@@ -12,17 +22,72 @@ impl Cmd {
             program: program.as_ref().to_os_string(),
             args: Vec::new(),
             envs: Vec::new(),
+            env_removes: Vec::new(),
+            env_clear: false,
+            env_keeps: Vec::new(),
             current_dir: None,
+            check_current_dir: false,
             suppress_echo: false,
+            allow_failure: false,
+            inherit: false,
+            stdout_redirect: None,
+            stderr_redirect: None,
+            label: None,
+            install_hint: None,
+            expand_arg_files: false,
+            arg0: None,
+            new_process_group: false,
+            uid: None,
+            gid: None,
+            kill_on_drop: false,
+            observers: Observers::default(),
         }
     }
 
+    /// Create a command from a command line, splitting it into words like a POSIX shell.
+    ///
+    /// Words are split with the same rules as [`expand_arg_files`](Self::expand_arg_files):
+    /// single quotes keep everything up to the closing quote literally, double
+    /// quotes keep their content together while honoring backslash escapes, and
+    /// a backslash outside quotes escapes the next character. The first word is
+    /// the program. No other shell syntax such as variables, globs or pipes is
+    /// interpreted.
+    ///
+    /// Returns an error if the line is empty or has an unterminated quote.
+    ///
+    /// ```no_run
+    /// use scriptify::*;
+    ///
+    /// // Runs `echo` with the three arguments `a b`, `c d` and `e f`
+    /// Cmd::parse(r#"echo "a b" 'c d' e\ f"#)?.run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn parse(line: &str) -> Result<Self, Error> {
+        let mut words = split_arg_file(line)
+            .ok_or_else(|| {
+                Error::invalid_argument(format!("Unterminated quote in command line: {line}"))
+            })?
+            .into_iter();
+        let program = words
+            .next()
+            .ok_or_else(|| Error::invalid_argument("Cannot parse an empty command line"))?;
+        Ok(Self::new(program).args(words))
+    }
+
     /// Add an argument.
     pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
         self.args.push(arg.as_ref().to_os_string());
         self
     }
 
+    /// Add an argument formatted with its `Display` implementation.
+    ///
+    /// Useful for numbers and other displayable values that are not `AsRef<OsStr>`,
+    /// e.g. `cmd!("head").arg("-n").arg_display(10)`.
+    pub fn arg_display(self, arg: impl std::fmt::Display) -> Self {
+        self.arg(arg.to_string())
+    }
+
     /// Add multiple arguments.
     pub fn args<I, S>(mut self, args: I) -> Self
     where
@@ -35,26 +100,292 @@ impl Cmd {
         self
     }
 
+    /// Add an argument only if `cond` is true.
+    ///
+    /// Keeps builders fluent, e.g. `cmd!("ls").arg("-l").arg_if(show_all, "-a")`.
+    pub fn arg_if(self, cond: bool, arg: impl AsRef<OsStr>) -> Self {
+        if cond { self.arg(arg) } else { self }
+    }
+
+    /// Add multiple arguments only if `cond` is true.
+    pub fn args_if<I, S>(self, cond: bool, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        if cond { self.args(args) } else { self }
+    }
+
+    /// Add an argument if it is `Some`, and nothing if it is `None`.
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let target = std::env::var("TARGET").ok();
+    /// cmd!("cargo", "build", "--target").arg_opt(target).run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn arg_opt(self, arg: Option<impl AsRef<OsStr>>) -> Self {
+        match arg {
+            Some(arg) => self.arg(arg),
+            None => self,
+        }
+    }
+
+    /// Set the name the program sees as `argv[0]`, instead of the program path.
+    ///
+    /// Some programs change their behavior based on `argv[0]`, e.g. multi-call
+    /// binaries like busybox or a shell started as a login shell with `-bash`.
+    /// The override is shown as `(argv0=...)` in the echo.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// // Start bash as a login shell
+    /// cmd!("bash", "-c", "echo $0").arg0("-bash").run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn arg0(mut self, name: impl AsRef<OsStr>) -> Self {
+        self.arg0 = Some(name.as_ref().to_os_string());
+        self
+    }
+
+    /// Start the command in a new process group (Unix only).
+    ///
+    /// The command becomes the leader of its own process group, so signals sent
+    /// to the script's group, such as `SIGINT` from Ctrl-C in a terminal, no
+    /// longer reach it. Combined with [`spawn`](Self::spawn) this lets a
+    /// long-running background process outlive the script.
+    ///
+    /// This uses [`CommandExt::process_group`](std::os::unix::process::CommandExt::process_group)
+    /// rather than a `pre_exec` hook calling `setsid` or `setpgid`: code run in
+    /// `pre_exec` executes in the forked child before `exec`, where only
+    /// async-signal-safe operations are sound, and must not allocate or take
+    /// locks. The standard library performs the `setpgid` call safely instead.
+    /// The command keeps the session and controlling terminal of the script.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let daemon = cmd!("mydaemon").new_process_group().spawn()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn new_process_group(mut self) -> Self {
+        self.new_process_group = true;
+        self
+    }
+
+    /// Run the command as the given user id (Unix only).
+    ///
+    /// Typically used by scripts running as root to drop privileges for a
+    /// sub-command. Spawning fails if the script lacks permission to switch to
+    /// the user. The id is shown as `(uid=...)` in the echo.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// cmd!("make", "install").uid(1000).gid(1000).run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(unix)]
+    pub fn uid(mut self, id: u32) -> Self {
+        self.uid = Some(id);
+        self
+    }
+
+    /// Run the command with the given group id (Unix only).
+    ///
+    /// See [`uid`](Self::uid). The id is shown as `(gid=...)` in the echo.
+    #[cfg(unix)]
+    pub fn gid(mut self, id: u32) -> Self {
+        self.gid = Some(id);
+        self
+    }
+
     /// Set an environment variable.
+    ///
+    /// Setting the same variable again replaces the earlier value, so only the
+    /// effective value is passed to the command and shown in the echo.
     pub fn env(mut self, key: impl AsRef<OsStr>, val: impl AsRef<OsStr>) -> Self {
+        let key = key.as_ref();
+        self.envs.retain(|(existing, _)| existing != key);
+        self.env_removes.retain(|existing| existing != key);
         self.envs
-            .push((key.as_ref().to_os_string(), val.as_ref().to_os_string()));
+            .push((key.to_os_string(), val.as_ref().to_os_string()));
+        self
+    }
+
+    /// Set multiple environment variables, in order.
+    ///
+    /// Each pair is applied as with [`env`](Self::env).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    /// use std::collections::BTreeMap;
+    ///
+    /// let config = BTreeMap::from([("APP_MODE", "release"), ("APP_PORT", "8080")]);
+    /// cmd!("./server").envs(config).run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn envs<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        for (key, val) in vars {
+            self = self.env(key, val);
+        }
         self
     }
 
+    /// Remove an environment variable the command would otherwise inherit.
+    ///
+    /// A value set earlier with [`env`](Self::env) is dropped as well. The removal
+    /// is shown as `env-rm:KEY` when the command is echoed.
+    pub fn env_remove(mut self, key: impl AsRef<OsStr>) -> Self {
+        let key = key.as_ref();
+        self.envs.retain(|(existing, _)| existing != key);
+        self.env_keeps.retain(|existing| existing != key);
+        if !self.env_clear && !self.env_removes.iter().any(|existing| existing == key) {
+            self.env_removes.push(key.to_os_string());
+        }
+        self
+    }
+
+    /// Start the command with an empty environment instead of inheriting this
+    /// process's.
+    ///
+    /// Variables set earlier with [`env`](Self::env) are dropped as well; set them
+    /// afterwards to pass them. The program is still looked up on this process's
+    /// `PATH` unless one is set for the command. Shown as `env-clear` when the
+    /// command is echoed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// // A hermetic build with only the variables it needs
+    /// cmd!("make").env_clear().env("HOME", "/tmp/build").run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn env_clear(mut self) -> Self {
+        self.envs.clear();
+        self.env_removes.clear();
+        self.env_keeps.clear();
+        self.env_clear = true;
+        self
+    }
+
+    /// Start the command with only the listed variables of this process's
+    /// environment.
+    ///
+    /// This is [`env_clear`](Self::env_clear) followed by passing each listed
+    /// variable through with the value it has when the command is spawned;
+    /// variables that are not set are skipped. Variables set afterwards with
+    /// [`env`](Self::env) are passed as well. Each kept variable is shown as
+    /// `env-keep:KEY` when the command is echoed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// // A reproducible build that only sees PATH and HOME
+    /// cmd!("cargo", "build").env_inherit_only(["PATH", "HOME"]).run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn env_inherit_only<I, K>(mut self, keys: I) -> Self
+    where
+        I: IntoIterator<Item = K>,
+        K: AsRef<OsStr>,
+    {
+        self = self.env_clear();
+        self.env_keeps = keys
+            .into_iter()
+            .map(|key| key.as_ref().to_os_string())
+            .collect();
+        self
+    }
+
+    /// Set `RUST_BACKTRACE` for a Rust program, to `full` or to `1`.
+    pub fn rust_backtrace(self, full: bool) -> Self {
+        self.env("RUST_BACKTRACE", if full { "full" } else { "1" })
+    }
+
+    /// Set `RUST_LOG` for a Rust program, e.g. `"info"` or `"my_crate=debug"`.
+    pub fn rust_log(self, directive: &str) -> Self {
+        self.env("RUST_LOG", directive)
+    }
+
+    /// Set `PATH` for the command to the current value of this process's `PATH`.
+    ///
+    /// Programs are looked up on `PATH` each time a command is spawned, so a
+    /// program installed mid-script (e.g. by `cargo install`) is found without
+    /// any caching. This method makes the lookup path explicit and visible in the
+    /// echoed command, which helps diagnose `PATH` changes made with
+    /// `std::env::set_var` during the script. If `PATH` is unset, nothing changes.
+    ///
+    /// When `PATH` is set with `.env("PATH", ...)`, that value is used for the lookup.
+    pub fn refresh_path(self) -> Self {
+        match std::env::var_os("PATH") {
+            Some(path) => self.env("PATH", path),
+            None => self,
+        }
+    }
+
     /// Set the working directory.
     pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
         self.current_dir = Some(dir.as_ref().to_path_buf());
+        self.check_current_dir = false;
+        self
+    }
+
+    /// Set the working directory, checking that it exists before the command is
+    /// spawned.
+    ///
+    /// A missing directory is reported as "Working directory does not exist"
+    /// rather than as a generic spawn failure.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// cmd!("cargo", "build").current_dir_checked("crates/app").run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn current_dir_checked(mut self, dir: impl AsRef<Path>) -> Self {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self.check_current_dir = true;
         self
     }
 
     /// Convert this command into a single-command pipeline.
     pub(crate) fn into_pipeline(self) -> Pipeline {
         let suppress_echo = self.suppress_echo;
+        let allow_failure = self.allow_failure;
         Pipeline {
             connections: vec![(self, PipeMode::Stdout)],
             input: None,
             suppress_echo,
+            allow_failure,
+            pipefail: true,
+            tail_lines: None,
+            tees: Vec::new(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            current_dir: None,
         }
     }
 
@@ -70,15 +401,19 @@ impl Cmd {
         self.into_pipeline().input_bytes_owned(bytes)
     }
 
-    /// Set text input for the command.
-    /// Optimized to convert string directly to bytes without intermediate allocation.
-    pub fn input(self, input: impl AsRef<str>) -> Pipeline {
+    /// Set input for the command from text or raw bytes.
+    ///
+    /// Accepts anything viewable as bytes, such as `&str`, `String` or
+    /// `&Vec<u8>`, so binary data is passed through unchanged.
+    pub fn input(self, input: impl AsRef<[u8]>) -> Pipeline {
         self.into_pipeline().input(input)
     }
 
-    /// Set input from a Reader (unbuffered).
-    /// Reads all data from the reader into memory.
-    /// For large files, consider using `input_buffered` instead.
+    /// Set input from a Reader.
+    /// The reader is streamed into the command's stdin from a separate thread,
+    /// so it is never read into memory as a whole.
+    ///
+    /// See [`Pipeline::input_reader`].
     pub fn input_reader<R: Read + Send + 'static>(self, reader: R) -> Pipeline {
         self.into_pipeline().input_reader(reader)
     }
@@ -89,19 +424,261 @@ impl Cmd {
         self.into_pipeline().input_buffered(reader)
     }
 
+    /// Use a file as the command's stdin, as with `cmd < path` in a shell.
+    ///
+    /// See [`Pipeline::stdin_from_file`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let sorted = cmd!("sort").stdin_from_file("names.txt").output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stdin_from_file(self, path: impl AsRef<Path>) -> Pipeline {
+        self.into_pipeline().stdin_from_file(path)
+    }
+
+    /// Keep only the last `n` lines of the captured output.
+    ///
+    /// See [`Pipeline::tail_lines`].
+    pub fn tail_lines(self, n: usize) -> Pipeline {
+        self.into_pipeline().tail_lines(n)
+    }
+
+    /// Set the size of the buffers used to move data in and out of the command.
+    ///
+    /// See [`Pipeline::buffer_size`].
+    pub fn buffer_size(self, size: usize) -> Pipeline {
+        self.into_pipeline().buffer_size(size)
+    }
+
+    /// Copy this command's output to a writer while piping it on.
+    ///
+    /// See [`Pipeline::tee_to`].
+    pub fn tee_to<W: Write + Send + 'static>(self, writer: W) -> Pipeline {
+        self.into_pipeline().tee_to(writer)
+    }
+
+    /// Stream a file into the command without reading it into memory.
+    ///
+    /// See [`Pipeline::input_file`].
+    pub fn input_file(self, path: impl AsRef<Path>) -> Pipeline {
+        self.into_pipeline().input_file(path)
+    }
+
+    /// Give the command an empty stdin, so it sees EOF instead of waiting for input.
+    ///
+    /// See [`Pipeline::stdin_null`].
+    pub fn stdin_null(self) -> Pipeline {
+        self.into_pipeline().stdin_null()
+    }
+
+    /// Feed the command from this process's stdin.
+    ///
+    /// See [`Pipeline::stdin_inherit`].
+    pub fn stdin_inherit(self) -> Pipeline {
+        self.into_pipeline().stdin_inherit()
+    }
+
+    /// Prefix each line of output with `[label] ` when the command is run.
+    ///
+    /// With a label set, `run()` streams the command's stdout and stderr line by line,
+    /// tagging every line so that output from commands running concurrently stays
+    /// legible (similar to `docker-compose` logs). In a pipeline, the label of the
+    /// last labelled stage is used. Captured output (`output()` etc.) is not modified.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    /// use std::thread;
+    ///
+    /// let web = thread::spawn(|| cmd!("npm", "run", "build").label("web").run());
+    /// let api = thread::spawn(|| cmd!("cargo", "build").label("api").run());
+    /// web.join().unwrap()?;
+    /// api.join().unwrap()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn label(mut self, label: &str) -> Self {
+        self.label = Some(label.to_string());
+        self
+    }
+
+    /// Attach an install hint shown when the program cannot be found.
+    ///
+    /// If spawning fails because the program does not exist, the hint is
+    /// appended to the error message, turning a cryptic failure into guidance
+    /// for users who are missing a dependency.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// cmd!("rg", "TODO")
+    ///     .install_hint("install with: brew install ripgrep")
+    ///     .run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn install_hint(mut self, hint: &str) -> Self {
+        self.install_hint = Some(hint.to_string());
+        self
+    }
+
+    /// Expand `@file` arguments into the contents of the referenced file when the
+    /// command is run.
+    ///
+    /// This is opt-in: without it, arguments starting with `@` are passed through
+    /// unchanged. With it, every such argument is replaced by the arguments read
+    /// from the file, split on whitespace and newlines; single and double quotes
+    /// group words containing whitespace, and a backslash escapes the next
+    /// character outside single quotes. Relative paths are resolved against the
+    /// command's working directory. The echoed command still shows `@file`.
+    ///
+    /// Useful when an argument list exceeds the OS limit and must be kept in a file,
+    /// regardless of whether the tool supports argument files itself.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// // sources.txt lists one source file per line
+    /// cmd!("cc", "-c", "@sources.txt").expand_arg_files().run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn expand_arg_files(mut self) -> Self {
+        self.expand_arg_files = true;
+        self
+    }
+
+    /// Write stdout to a file, truncating it, as with `cmd > path` in a shell.
+    ///
+    /// The file is handed to the command directly, so the output is never
+    /// buffered in memory. The redirection is shown when the command is echoed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// cmd!("dmesg").stdout_to("kernel.log").run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stdout_to(mut self, path: impl AsRef<Path>) -> Self {
+        self.stdout_redirect = Some(Redirect::File {
+            path: path.as_ref().to_path_buf(),
+            append: false,
+        });
+        self
+    }
+
+    /// Append stdout to a file, as with `cmd >> path` in a shell.
+    ///
+    /// See [`stdout_to`](Self::stdout_to).
+    pub fn stdout_append(mut self, path: impl AsRef<Path>) -> Self {
+        self.stdout_redirect = Some(Redirect::File {
+            path: path.as_ref().to_path_buf(),
+            append: true,
+        });
+        self
+    }
+
+    /// Discard stdout, as with `cmd > /dev/null` in a shell.
+    ///
+    /// Unlike [`no_echo`](Self::no_echo), which only hides scriptify's own echo
+    /// of the command, this silences the command itself. The redirection is
+    /// shown when the command is echoed.
+    pub fn stdout_to_null(mut self) -> Self {
+        self.stdout_redirect = Some(Redirect::Null);
+        self
+    }
+
+    /// Discard stderr, as with `cmd 2> /dev/null` in a shell.
+    ///
+    /// See [`stdout_to_null`](Self::stdout_to_null).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// cmd!("find", "/", "-name", "*.conf").stderr_to_null().run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stderr_to_null(mut self) -> Self {
+        self.stderr_redirect = Some(Redirect::Null);
+        self
+    }
+
     /// Run without echoing the command.
     pub fn no_echo(mut self) -> Self {
         self.suppress_echo = true;
         self
     }
 
+    /// Don't treat a non-zero exit as an error.
+    ///
+    /// Methods like `run()` and `output()` then succeed regardless of the exit
+    /// code; failing to spawn the command is still an error. Use
+    /// [`status`](Self::status) to find out how the command exited.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// // diff exits with 1 when the files differ
+    /// cmd!("diff", "a.txt", "b.txt").allow_failure().run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn allow_failure(mut self) -> Self {
+        self.allow_failure = true;
+        self
+    }
+
+    /// Let the command write stdout and stderr directly to this process's
+    /// stdout and stderr instead of to pipes.
+    ///
+    /// [`run`](Self::run) and [`status`](Self::status) already do this, so that
+    /// interactive and progress output (e.g. from `cargo build`) is shown live;
+    /// this method makes it explicit. With methods that read the output, such
+    /// as [`output`](Self::output) or [`stream_to`](Self::stream_to), the
+    /// inherited streams are not captured. In a pipeline only the last
+    /// command's setting has an effect.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// cmd!("cargo", "build", "--release").inherit().run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn inherit(mut self) -> Self {
+        self.inherit = true;
+        self
+    }
+
     /// Pipe this command to another command.
+    ///
+    /// Both commands keep all of their own options (environment, working directory,
+    /// redirections, labels, ...) as stages of the resulting pipeline.
     pub fn pipe(self, next: Cmd) -> Pipeline {
         let suppress_echo = self.suppress_echo || next.suppress_echo;
+        let allow_failure = self.allow_failure || next.allow_failure;
         Pipeline {
             connections: vec![(self, PipeMode::Stdout), (next, PipeMode::Stdout)],
             input: None,
             suppress_echo,
+            allow_failure,
+            pipefail: true,
+            tail_lines: None,
+            tees: Vec::new(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            current_dir: None,
         }
     }
 
@@ -123,51 +700,179 @@ impl Cmd {
     /// ```
     pub fn pipe_stderr(self, next: Cmd) -> Pipeline {
         let suppress_echo = self.suppress_echo || next.suppress_echo;
+        let allow_failure = self.allow_failure || next.allow_failure;
         Pipeline {
             connections: vec![(self, PipeMode::Stdout), (next, PipeMode::Stderr)],
             input: None,
             suppress_echo,
+            allow_failure,
+            pipefail: true,
+            tail_lines: None,
+            tees: Vec::new(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            current_dir: None,
         }
     }
 
-    /// Pipe this command's combined stdout and stderr to another command's stdin.
+    /// Pipe this command's combined stdout and stderr to another command's stdin.
+    ///
+    /// This is a convenience method that creates a pipeline with PipeMode::Both,
+    /// allowing you to specify the pipe mode directly in the builder pattern.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// // Sort all output (both stdout and stderr)
+    /// let sorted_output = cmd!("sh", "-c", "echo 'out'; echo 'err' >&2")
+    ///     .pipe_both(cmd!("sort"))
+    ///     .output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn pipe_both(self, next: Cmd) -> Pipeline {
+        let suppress_echo = self.suppress_echo || next.suppress_echo;
+        let allow_failure = self.allow_failure || next.allow_failure;
+        Pipeline {
+            connections: vec![(self, PipeMode::Stdout), (next, PipeMode::Both)],
+            input: None,
+            suppress_echo,
+            allow_failure,
+            pipefail: true,
+            tail_lines: None,
+            tees: Vec::new(),
+            buffer_size: DEFAULT_BUFFER_SIZE,
+            current_dir: None,
+        }
+    }
+
+    /// Run the command.
+    ///
+    /// The command's stdout and stderr are not captured but inherited, so its
+    /// output appears live on the terminal. A non-zero exit is an error.
+    pub fn run(self) -> Result<(), Error> {
+        self.into_pipeline().run()
+    }
+
+    /// Run the command and return its exit status.
+    ///
+    /// A non-zero exit is not an error; see [`Pipeline::status`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let status = cmd!("diff", "a.txt", "b.txt").status()?;
+    /// if status.code() == Some(1) {
+    ///     println!("files differ");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn status(self) -> Result<Status, Error> {
+        self.into_pipeline().status()
+    }
+
+    /// Get stdout and stderr as separate strings.
+    ///
+    /// See [`Pipeline::output_split`].
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let (stdout, stderr) = cmd!("cargo", "metadata", "--format-version=1").output_split()?;
+    /// if !stderr.is_empty() {
+    ///     eprintln!("warnings: {stderr}");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn output_split(self) -> Result<(String, String), Error> {
+        self.into_pipeline().output_split()
+    }
+
+    /// Run the command, capturing its output, and return it together with the
+    /// plain-text command line as it is echoed.
+    ///
+    /// See [`Pipeline::run_traced`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let (output, rendered) = cmd!("echo", "a b").no_echo().run_traced()?;
+    /// assert_eq!(rendered, "echo 'a b'");
+    /// assert_eq!(output.stdout, b"a b\n");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn run_traced(self) -> Result<(CmdOutput, String), Error> {
+        self.into_pipeline().run_traced()
+    }
+
+    /// Get binary output from the command, without any UTF-8 conversion.
+    ///
+    /// See [`Pipeline::output_bytes`] for details.
+    pub fn output_bytes(self) -> Result<Vec<u8>, Error> {
+        self.into_pipeline().output_bytes()
+    }
+
+    /// Get binary output from the selected stream(s) of the command.
+    ///
+    /// See [`Pipeline::output_bytes_of`] for details.
+    pub fn output_bytes_of(self, which: PipeMode) -> Result<Vec<u8>, Error> {
+        self.into_pipeline().output_bytes_of(which)
+    }
+
+    /// Get text output from the command.
+    ///
+    /// The output is not trimmed; see [`output_raw`](Self::output_raw). Invalid
+    /// UTF-8 is replaced with `U+FFFD`; use [`output_bytes`](Self::output_bytes)
+    /// to get the raw bytes.
+    pub fn output(self) -> Result<String, Error> {
+        self.into_pipeline().output()
+    }
+
+    /// Get text output from the command together with its exit status.
     ///
-    /// This is a convenience method that creates a pipeline with PipeMode::Both,
-    /// allowing you to specify the pipe mode directly in the builder pattern.
+    /// A non-zero exit is not an error; see [`Pipeline::output_with_status`].
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use scriptify::cmd;
     ///
-    /// // Sort all output (both stdout and stderr)
-    /// let sorted_output = cmd!("sh", "-c", "echo 'out'; echo 'err' >&2")
-    ///     .pipe_both(cmd!("sort"))
-    ///     .output()?;
+    /// let (changes, status) = cmd!("git", "diff", "--stat", "--exit-code").output_with_status()?;
+    /// if !status.success() {
+    ///     print!("{changes}");
+    /// }
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn pipe_both(self, next: Cmd) -> Pipeline {
-        let suppress_echo = self.suppress_echo || next.suppress_echo;
-        Pipeline {
-            connections: vec![(self, PipeMode::Stdout), (next, PipeMode::Both)],
-            input: None,
-            suppress_echo,
-        }
+    pub fn output_with_status(self) -> Result<(String, Status), Error> {
+        self.into_pipeline().output_with_status()
     }
 
-    /// Run the command and return the exit status.
-    pub fn run(self) -> Result<(), Error> {
-        self.into_pipeline().run()
+    /// Get text output from the command exactly as it was written.
+    ///
+    /// See [`Pipeline::output_raw`] for details.
+    pub fn output_raw(self) -> Result<String, Error> {
+        self.into_pipeline().output_raw()
     }
 
-    /// Get binary output from the command.
-    pub fn output_bytes(self) -> Result<Vec<u8>, Error> {
-        self.into_pipeline().output_bytes()
+    /// Get text output from the command, decoded from the encoding named by `label`.
+    ///
+    /// See [`Pipeline::output_with_encoding`] for details.
+    #[cfg(feature = "encoding")]
+    pub fn output_with_encoding(self, label: &str) -> Result<String, Error> {
+        self.into_pipeline().output_with_encoding(label)
     }
 
-    /// Get text output from the command.
-    pub fn output(self) -> Result<String, Error> {
-        self.into_pipeline().output()
+    /// Get text output from the command with `\r\n` line endings converted to `\n`.
+    ///
+    /// See [`Pipeline::output_normalized`] for details.
+    pub fn output_normalized(self) -> Result<String, Error> {
+        self.into_pipeline().output_normalized()
     }
 
     /// Run the command and stream output to a Writer.
@@ -176,6 +881,37 @@ impl Cmd {
         self.into_pipeline().stream_to(writer)
     }
 
+    /// Run the command, streaming its output to a Writer while also returning it.
+    ///
+    /// See [`Pipeline::tee`] for details.
+    pub fn tee<W: Write>(self, writer: W) -> Result<String, Error> {
+        self.into_pipeline().tee(writer)
+    }
+
+    /// Run the command, calling `f` with each line of its output as it arrives.
+    ///
+    /// See [`Pipeline::for_each_line`] for details.
+    pub fn for_each_line<F>(self, f: F) -> Result<(), Error>
+    where
+        F: FnMut(&str) -> std::ops::ControlFlow<()>,
+    {
+        self.into_pipeline().for_each_line(f)
+    }
+
+    /// Pass the output of this command as arguments to `cmd`, like `xargs`.
+    ///
+    /// See [`Pipeline::xargs`] for details.
+    pub fn xargs(self, cmd: Cmd) -> Xargs {
+        self.into_pipeline().xargs(cmd)
+    }
+
+    /// Start the command and return a reader over its output.
+    ///
+    /// See [`Pipeline::reader`] for details.
+    pub fn reader(self) -> Result<PipelineReader, Error> {
+        self.into_pipeline().reader()
+    }
+
     /// Run the command with both input Reader and output Writer.
     /// This is the most flexible method for streaming I/O.
     pub fn run_with_io<R: Read + Send + 'static, W: Write>(
@@ -226,6 +962,488 @@ impl Cmd {
         self.into_pipeline().spawn_with_both()
     }
 
+    /// Kill the command when the handle returned by [`spawn`](Self::spawn) or
+    /// [`capture_into`](Self::capture_into) is dropped while it is still running.
+    ///
+    /// Without this, a background command keeps running if its handle is dropped,
+    /// e.g. when a test fails or returns early before waiting for it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let _server = cmd!("python3", "-m", "http.server", "8000").kill_on_drop(true).spawn()?;
+    /// cmd!("curl", "--retry", "5", "--retry-connrefused", "http://localhost:8000/").run()?;
+    /// // The server is killed when `_server` goes out of scope
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn kill_on_drop(mut self, kill_on_drop: bool) -> Self {
+        self.kill_on_drop = kill_on_drop;
+        self
+    }
+
+    /// Call `f` with the process id of the command right after it is spawned.
+    ///
+    /// Together with [`on_exit`](Self::on_exit) this allows instrumenting
+    /// commands, e.g. for metrics or audit logs. Nothing is called for commands
+    /// that are not actually started, as in dry-run mode.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// cmd!("cargo", "build")
+    ///     .on_spawn(|pid| eprintln!("started pid {pid}"))
+    ///     .run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn on_spawn(mut self, f: impl Fn(u32) + Send + Sync + 'static) -> Self {
+        self.observers.on_spawn = Some(Arc::new(f));
+        self
+    }
+
+    /// Call `f` with the exit status of the command and how long it ran, once it
+    /// has exited and has been waited for.
+    ///
+    /// In a pipeline each command reports its own status. The callback is called
+    /// whether or not the command succeeded, before a failure is returned as an
+    /// error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// cmd!("cargo", "test")
+    ///     .on_exit(|status, elapsed| eprintln!("tests: {status} in {elapsed:?}"))
+    ///     .run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn on_exit(mut self, f: impl Fn(&Status, Duration) + Send + Sync + 'static) -> Self {
+        self.observers.on_exit = Some(Arc::new(f));
+        self
+    }
+
+    /// Start the command in the background and return a handle to wait for or
+    /// kill it.
+    ///
+    /// Stdin, stdout and stderr are inherited unless redirected. This suits
+    /// long-running processes such as a server used by integration tests.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let mut server = cmd!("python3", "-m", "http.server", "8000").spawn()?;
+    /// cmd!("curl", "--retry", "5", "--retry-connrefused", "http://localhost:8000/").run()?;
+    /// server.kill()?;
+    /// server.wait()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn spawn(self) -> Result<CmdHandle, Error> {
+        let mut pipeline = self.into_pipeline();
+        if !pipeline.suppress_echo {
+            pipeline.echo_pipeline();
+        }
+        if record::skip_execution(&pipeline) {
+            return Ok(CmdHandle {
+                child: None,
+                kill_on_drop: false,
+                watch: None,
+            });
+        }
+        let cmd = &mut pipeline.connections[0].0;
+        cmd.prepare()?;
+
+        let mut std_cmd = Pipeline::build_std_command_static(cmd);
+        cmd.apply_redirects(&mut std_cmd)?;
+
+        let child = std_cmd.spawn().map_err(|e| cmd.spawn_error(e))?;
+        let watch = cmd.observers.spawned(&child);
+        Ok(CmdHandle {
+            child: Some(child),
+            kill_on_drop: cmd.kill_on_drop,
+            watch: Some(watch),
+        })
+    }
+
+    /// Spawn the command and stream its stdout into a shared buffer.
+    ///
+    /// A background thread appends output to `buf` as it arrives, so other threads
+    /// can inspect the buffer while the command is still running (e.g. to live-tail it).
+    /// The command's stdin is connected to null and its stderr is inherited.
+    /// Redirects set with [`stdout_to`](Self::stdout_to) or similar still apply,
+    /// so stdout redirected that way never reaches `buf`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// let buf = Arc::new(Mutex::new(Vec::new()));
+    /// let handle = cmd!("ping", "-c", "3", "localhost").capture_into(Arc::clone(&buf))?;
+    /// // ... inspect `buf` periodically from this or another thread ...
+    /// handle.wait()?;
+    /// println!("{}", String::from_utf8_lossy(&buf.lock().unwrap()));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_into(self, buf: Arc<Mutex<Vec<u8>>>) -> Result<ChildHandle, Error> {
+        let mut pipeline = self.into_pipeline();
+        if !pipeline.suppress_echo {
+            pipeline.echo_pipeline();
+        }
+        if record::skip_execution(&pipeline) {
+            return Ok(ChildHandle {
+                child: None,
+                reader: None,
+                kill_on_drop: false,
+                allow_failure: pipeline.allow_failure,
+                watch: None,
+                rendered: String::new(),
+            });
+        }
+        let allow_failure = pipeline.allow_failure;
+        let rendered = pipeline.render(false);
+        let cmd = &mut pipeline.connections[0].0;
+        cmd.prepare()?;
+
+        let mut std_cmd = Pipeline::build_std_command_static(cmd);
+        std_cmd.stdin(Stdio::null());
+        std_cmd.stdout(Stdio::piped());
+        cmd.apply_redirects(&mut std_cmd)?;
+
+        let mut child = std_cmd.spawn().map_err(|e| cmd.spawn_error(e))?;
+        let watch = cmd.observers.spawned(&child);
+
+        let reader = child
+            .stdout
+            .take()
+            .map(|stdout| collect_stream(stdout, Some(buf), DEFAULT_BUFFER_SIZE));
+
+        Ok(ChildHandle {
+            child: Some(child),
+            reader,
+            kill_on_drop: cmd.kill_on_drop,
+            allow_failure,
+            watch: Some(watch),
+            rendered,
+        })
+    }
+
+    /// Run the command with stdout and stderr written to the given files.
+    ///
+    /// This is the equivalent of `cmd >stdout_path 2>stderr_path`: both streams are
+    /// written directly to the files as the command runs, without buffering in memory.
+    /// The exit status is returned as-is, even when it is non-zero, so both logs are
+    /// always written; only failures to open the files or spawn the command are errors.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let status = cmd!("cargo", "test").capture_to_files("test.out.log", "test.err.log")?;
+    /// if !status.success() {
+    ///     eprintln!("tests failed, see test.err.log");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn capture_to_files(
+        mut self,
+        stdout_path: impl AsRef<Path>,
+        stderr_path: impl AsRef<Path>,
+    ) -> Result<ExitStatus, Error> {
+        self.stdout_redirect = Some(Redirect::File {
+            path: stdout_path.as_ref().to_path_buf(),
+            append: false,
+        });
+        self.stderr_redirect = Some(Redirect::File {
+            path: stderr_path.as_ref().to_path_buf(),
+            append: false,
+        });
+
+        let mut pipeline = self.into_pipeline();
+        if !pipeline.suppress_echo {
+            pipeline.echo_pipeline();
+        }
+        if record::skip_execution(&pipeline) {
+            return Ok(ExitStatus::default());
+        }
+        let cmd = &mut pipeline.connections[0].0;
+        cmd.prepare()?;
+
+        let mut std_cmd = Pipeline::build_std_command_static(cmd);
+        cmd.apply_redirects(&mut std_cmd)?;
+
+        let mut child = std_cmd.spawn().map_err(|e| cmd.spawn_error(e))?;
+        let watch = cmd.observers.spawned(&child);
+
+        let status = child
+            .wait()
+            .map_err(|e| Error::io("Failed to wait for child process", e))?;
+        watch.exited(status);
+        Ok(status)
+    }
+
+    /// Run the command with stdout streaming to the terminal while stderr is captured.
+    ///
+    /// Progress written to stdout is shown live, and if the command exits with a
+    /// non-zero status the returned `Error` contains what it wrote to stderr, so
+    /// only the relevant error output is reported. A stream redirected with
+    /// [`stdout_to`](Self::stdout_to) or similar goes to its target instead, and
+    /// stderr redirected that way is not captured.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// if let Err(e) = cmd!("make", "all").inherit_stdout_capture_stderr() {
+    ///     eprintln!("build failed: {e}");
+    /// }
+    /// ```
+    pub fn inherit_stdout_capture_stderr(self) -> Result<ExitStatus, Error> {
+        let mut pipeline = self.into_pipeline();
+        if !pipeline.suppress_echo {
+            pipeline.echo_pipeline();
+        }
+        if record::skip_execution(&pipeline) {
+            return Ok(ExitStatus::default());
+        }
+        let allow_failure = pipeline.allow_failure;
+        let rendered = pipeline.render(false);
+        let cmd = &mut pipeline.connections[0].0;
+        cmd.prepare()?;
+
+        let mut std_cmd = Pipeline::build_std_command_static(cmd);
+        std_cmd.stderr(Stdio::piped());
+        cmd.apply_redirects(&mut std_cmd)?;
+
+        let mut child = std_cmd.spawn().map_err(|e| cmd.spawn_error(e))?;
+        let watch = cmd.observers.spawned(&child);
+
+        let mut stderr = Vec::new();
+        if let Some(mut pipe) = child.stderr.take() {
+            pipe.read_to_end(&mut stderr)
+                .map_err(|e| Error::io("Failed to read stderr", e))?;
+        }
+
+        let status = child
+            .wait()
+            .map_err(|e| Error::io("Failed to wait for child process", e))?;
+        watch.exited(status);
+
+        if !allow_failure && !status.success() {
+            return Err(Error::exit(
+                &status,
+                Vec::new(),
+                String::from_utf8_lossy(&stderr).into_owned(),
+            )
+            .with_command(&rendered));
+        }
+        Ok(status)
+    }
+
+    /// Run the command with stdin inherited from this process and stdout captured.
+    ///
+    /// The command is echoed as usual and the user can type into it, which suits
+    /// wrapping interactive programs whose result is needed afterwards. Stderr is
+    /// inherited, so prompts written there remain visible.
+    ///
+    /// Because stdout is captured, the user does not see what the program writes
+    /// to stdout while it runs; pipe it through `tee /dev/tty` if it must also be
+    /// shown live. A stream redirected with [`stdout_to`](Self::stdout_to) or
+    /// similar goes to its target instead, and stdout redirected that way is not
+    /// captured.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// // Let the user pick a file interactively and use the selection
+    /// let selected = cmd!("fzf").interactive_capture()?;
+    /// println!("selected: {}", selected.trim());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn interactive_capture(self) -> Result<String, Error> {
+        let mut pipeline = self.into_pipeline();
+        if !pipeline.suppress_echo {
+            pipeline.echo_pipeline();
+        }
+        if record::skip_execution(&pipeline) {
+            return Ok(String::new());
+        }
+        let allow_failure = pipeline.allow_failure;
+        let rendered = pipeline.render(false);
+        let cmd = &mut pipeline.connections[0].0;
+        cmd.prepare()?;
+
+        let mut std_cmd = Pipeline::build_std_command_static(cmd);
+        std_cmd.stdin(Stdio::inherit());
+        std_cmd.stdout(Stdio::piped());
+        std_cmd.stderr(Stdio::inherit());
+        cmd.apply_redirects(&mut std_cmd)?;
+
+        let mut child = std_cmd.spawn().map_err(|e| cmd.spawn_error(e))?;
+        let watch = cmd.observers.spawned(&child);
+
+        let mut stdout = Vec::new();
+        if let Some(mut pipe) = child.stdout.take() {
+            pipe.read_to_end(&mut stdout)
+                .map_err(|e| Error::io("Failed to read stdout", e))?;
+        }
+
+        let status = child
+            .wait()
+            .map_err(|e| Error::io("Failed to wait for child process", e))?;
+        watch.exited(status);
+
+        if !allow_failure && !status.success() {
+            return Err(Error::exit(&status, stdout, String::new()).with_command(&rendered));
+        }
+        Ok(String::from_utf8_lossy(&stdout).into_owned())
+    }
+
+    /// Convert into a [`std::process::Command`] to use a feature scriptify
+    /// doesn't wrap, and spawn it yourself.
+    ///
+    /// The program, arguments, environment, working directory and the Unix
+    /// process settings are carried over. Everything scriptify does around
+    /// running the command is not: it is not echoed or recorded and ignores
+    /// dry-run mode, input and output redirections are not applied, argument
+    /// files are not expanded and the working directory is not checked.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let mut command = cmd!("make", "-j8").env("CC", "clang").into_std();
+    /// let status = command.status()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn into_std(self) -> std::process::Command {
+        Pipeline::build_std_command_static(&self)
+    }
+
+    /// Point stdout and stderr at their redirect targets, overriding any pipes
+    /// configured for them.
+    pub(crate) fn apply_redirects(&self, std_cmd: &mut std::process::Command) -> Result<(), Error> {
+        if let Some(redirect) = &self.stdout_redirect {
+            std_cmd.stdout(redirect.open()?);
+        }
+        if let Some(redirect) = &self.stderr_redirect {
+            std_cmd.stderr(redirect.open()?);
+        }
+        Ok(())
+    }
+
+    /// Build the error for a failed spawn, saying so if the program is not on
+    /// `PATH` and including the install hint if the program was not found.
+    pub(crate) fn spawn_error(&self, err: std::io::Error) -> Error {
+        let program = self.program.to_string_lossy();
+        let mut message = format!("Failed to spawn command: {program}");
+        if err.kind() == std::io::ErrorKind::NotFound {
+            // Look the program up on the PATH the command was given
+            let path = match self.envs.iter().rev().find(|(key, _)| key == "PATH") {
+                Some((_, path)) => Some(path.clone()),
+                None if self.env_removes.iter().any(|key| key == "PATH") => None,
+                None if self.env_clear && !self.env_keeps.iter().any(|key| key == "PATH") => None,
+                None => std::env::var_os("PATH"),
+            };
+            if crate::which::which_in(&self.program, path.as_deref()).is_none() {
+                message = format!("Failed to spawn command: program '{program}' not found on PATH");
+            }
+            if let Some(hint) = &self.install_hint {
+                message.push_str(&format!(" ({})", hint));
+            }
+        }
+        Error::spawn(message, Some(err))
+    }
+
+    /// Expand argument files if requested and validate the command before spawning.
+    pub(crate) fn prepare(&mut self) -> Result<(), Error> {
+        if self.expand_arg_files {
+            self.args = self.expanded_args()?;
+            self.expand_arg_files = false;
+        }
+        self.validate()
+    }
+
+    /// Replace each `@file` argument with the arguments read from that file.
+    fn expanded_args(&self) -> Result<Vec<std::ffi::OsString>, Error> {
+        let mut args = Vec::with_capacity(self.args.len());
+        for arg in &self.args {
+            let Some(file) = arg.to_str().and_then(|a| a.strip_prefix('@')) else {
+                args.push(arg.clone());
+                continue;
+            };
+            if file.is_empty() {
+                args.push(arg.clone());
+                continue;
+            }
+
+            let path = match &self.current_dir {
+                Some(dir) => dir.join(file),
+                None => Path::new(file).to_path_buf(),
+            };
+            let contents = std::fs::read_to_string(&path).map_err(|e| {
+                Error::io(
+                    format!("Failed to read argument file: {}", path.display()),
+                    e,
+                )
+            })?;
+            let words = split_arg_file(&contents).ok_or_else(|| {
+                Error::invalid_argument(format!(
+                    "Invalid argument file {}: unterminated quote",
+                    path.display()
+                ))
+            })?;
+            args.extend(words.into_iter().map(Into::into));
+        }
+        Ok(args)
+    }
+
+    /// Check that the program and arguments can be passed to the OS.
+    ///
+    /// An interior NUL byte would otherwise make the spawn fail with an opaque
+    /// system error, so it is reported up front, naming the offending value.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        let has_nul = |s: &OsStr| s.as_encoded_bytes().contains(&0);
+
+        if has_nul(&self.program) {
+            return Err(Error::invalid_argument(format!(
+                "Invalid program name {}: contains an interior NUL byte",
+                Self::quote_argument(&self.program)
+            )));
+        }
+
+        if let Some(index) = self.args.iter().position(|arg| has_nul(arg)) {
+            return Err(Error::invalid_argument(format!(
+                "Invalid argument {} of {}: {} contains an interior NUL byte",
+                index + 1,
+                self.program.to_string_lossy(),
+                Self::quote_argument(&self.args[index])
+            )));
+        }
+
+        if let Some(dir) = self.current_dir.as_ref().filter(|_| self.check_current_dir) {
+            if !dir.is_dir() {
+                return Err(Error::spawn(
+                    format!("Working directory does not exist: {}", dir.display()),
+                    None,
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Quotes an argument for display if it contains characters that affect readability.  
     ///
     /// This function focuses on readability rather than shell compatibility:
@@ -301,3 +1519,196 @@ impl Cmd {
         arg_str.to_string()
     }
 }
+
+/// Formats the command as the plain command line it is echoed as, without
+/// running it.
+///
+/// ```
+/// use scriptify::cmd;
+///
+/// assert_eq!(cmd!("echo", "a b").to_string(), "echo 'a b'");
+/// ```
+impl std::fmt::Display for Cmd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.clone().into_pipeline().fmt(f)
+    }
+}
+
+impl CmdHandle {
+    /// The OS process id, or `None` if the command was not actually started
+    /// (see [`record`](crate::record) and [`set_dry_run`](crate::set_dry_run)).
+    pub fn id(&self) -> Option<u32> {
+        self.child.as_ref().map(|child| child.id())
+    }
+
+    /// Kill the command. Killing a command that has already exited is not an error.
+    pub fn kill(&mut self) -> Result<(), Error> {
+        let Some(child) = self.child.as_mut() else {
+            return Ok(());
+        };
+        child
+            .kill()
+            .map_err(|e| Error::io("Failed to kill child process", e))
+    }
+
+    /// Wait for the command to exit and return its status.
+    ///
+    /// A non-zero exit is not an error, so the status of a killed command can be
+    /// inspected.
+    pub fn wait(&mut self) -> Result<Status, Error> {
+        let Some(child) = self.child.as_mut() else {
+            return Ok(Status::from(ExitStatus::default()));
+        };
+        let status = child
+            .wait()
+            .map_err(|e| Error::io("Failed to wait for child process", e))?;
+        // Report the exit only the first time the command is waited for
+        if let Some(watch) = self.watch.take() {
+            watch.exited(status);
+        }
+        Ok(Status::from(status))
+    }
+}
+
+impl ChildHandle {
+    /// Wait for the command to exit and for all of its output to be collected.
+    pub fn wait(mut self) -> Result<(), Error> {
+        let Some(mut child) = self.child.take() else {
+            return Ok(());
+        };
+        let status = child
+            .wait()
+            .map_err(|e| Error::io("Failed to wait for child process", e))?;
+        if let Some(watch) = self.watch.take() {
+            watch.exited(status);
+        }
+
+        if let Some(reader) = self.reader.take() {
+            reader
+                .join()
+                .unwrap_or_else(|_| Err(std::io::Error::other("stdout reader panicked")))
+                .map_err(|e| Error::io("Failed to read stdout", e))?;
+        }
+
+        if !self.allow_failure && !status.success() {
+            return Err(
+                Error::exit(&status, Vec::new(), String::new()).with_command(&self.rendered)
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Observers {
+    /// Report that `child` was spawned and start timing it.
+    pub(crate) fn spawned(&self, child: &std::process::Child) -> ExitWatch {
+        if let Some(on_spawn) = &self.on_spawn {
+            on_spawn(child.id());
+        }
+        ExitWatch {
+            started: Instant::now(),
+            on_exit: self.on_exit.clone(),
+        }
+    }
+}
+
+impl ExitWatch {
+    /// Report that the command exited with `status`.
+    pub(crate) fn exited(&self, status: ExitStatus) {
+        if let Some(on_exit) = &self.on_exit {
+            on_exit(&Status::from(status), self.started.elapsed());
+        }
+    }
+}
+
+impl Drop for CmdHandle {
+    fn drop(&mut self) {
+        if let (true, Some(child)) = (self.kill_on_drop, &mut self.child) {
+            kill_if_running(child);
+        }
+    }
+}
+
+impl Drop for ChildHandle {
+    fn drop(&mut self) {
+        if let (true, Some(child)) = (self.kill_on_drop, &mut self.child) {
+            kill_if_running(child);
+        }
+    }
+}
+
+/// Kill and reap a child that has not exited yet.
+pub(crate) fn kill_if_running(child: &mut std::process::Child) {
+    if let Ok(None) = child.try_wait() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// Split the contents of an argument file or a command line into arguments.
+///
+/// Returns `None` if a quote is left unterminated.
+fn split_arg_file(contents: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = contents.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                words.extend(current.take());
+            }
+            '\\' => {
+                // A trailing backslash escapes nothing and is kept, as in a shell
+                let word = current.get_or_insert_with(String::new);
+                word.push(chars.next().unwrap_or('\\'));
+            }
+            '\'' => {
+                let word = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => word.push(chars.next()?),
+                        c => word.push(c),
+                    }
+                }
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(current);
+    Some(words)
+}
+
+impl Redirect {
+    /// Open the redirect target as a `Stdio` for a child process.
+    pub(crate) fn open(&self) -> Result<Stdio, Error> {
+        match self {
+            Redirect::File { path, append } => {
+                let file = std::fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .append(*append)
+                    .truncate(!*append)
+                    .open(path)
+                    .map_err(|e| {
+                        Error::io(
+                            format!("Failed to open redirect file: {}", path.display()),
+                            e,
+                        )
+                    })?;
+                Ok(Stdio::from(file))
+            }
+            Redirect::Null => Ok(Stdio::null()),
+        }
+    }
+}
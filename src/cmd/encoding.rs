@@ -0,0 +1,36 @@
+//! Decoding of command output in legacy single-byte encodings.
+
+use crate::cmd::error::Error;
+
+/// Windows-1252 characters for bytes 0x80..=0x9F; unassigned bytes map to the
+/// C1 control of the same value, as in the WHATWG encoding standard.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// Decode `bytes` using the encoding named by `label` (case-insensitive).
+///
+/// Supported labels are `utf-8` (invalid sequences are replaced), `latin1` /
+/// `iso-8859-1` and `windows-1252` / `cp1252`.
+pub(crate) fn decode(bytes: &[u8], label: &str) -> Result<String, Error> {
+    match label.trim().to_ascii_lowercase().as_str() {
+        "utf-8" | "utf8" => Ok(String::from_utf8_lossy(bytes).into_owned()),
+        "latin1" | "latin-1" | "iso-8859-1" | "iso8859-1" | "l1" => {
+            Ok(bytes.iter().map(|&b| char::from(b)).collect())
+        }
+        "windows-1252" | "cp1252" => Ok(bytes
+            .iter()
+            .map(|&b| match b {
+                0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(b - 0x80)],
+                _ => char::from(b),
+            })
+            .collect()),
+        _ => Err(Error::invalid_argument(format!(
+            "Unsupported encoding: {}",
+            label
+        ))),
+    }
+}
@@ -3,8 +3,182 @@
 /// Command execution error.
 #[derive(Debug)]
 pub struct Error {
+    pub(crate) kind: ErrorKind,
     pub(crate) message: String,
     pub(crate) source: Option<std::io::Error>,
+    pub(crate) partial_stdout: Vec<u8>,
+    pub(crate) exit_code: Option<i32>,
+    pub(crate) stderr: String,
+}
+
+/// The category of an [`Error`], returned by [`Error::kind`].
+///
+/// More kinds may be added in the future.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The command could not be started, e.g. because the program was not
+    /// found or its working directory does not exist.
+    SpawnFailed,
+    /// The command exited with a non-zero code or was terminated by a signal.
+    NonZeroExit,
+    /// A deadline passed, e.g. in [`wait_for`](crate::wait_for).
+    Timeout,
+    /// Reading, writing or waiting for a command failed.
+    Io,
+    /// The pipes connecting the commands of a pipeline could not be created.
+    PipeSetup,
+    /// The command was not valid, e.g. an argument contains a NUL byte.
+    InvalidArgument,
+}
+
+/// How much of the captured stderr is shown when an error is displayed.
+const DISPLAYED_STDERR_LEN: usize = 2048;
+
+impl Error {
+    /// What kind of failure this is, for handling errors without matching on
+    /// their message.
+    ///
+    /// ```no_run
+    /// use scriptify::{ErrorKind, cmd};
+    ///
+    /// match cmd!("rg", "TODO").run() {
+    ///     Err(e) if e.kind() == ErrorKind::SpawnFailed => cmd!("grep", "-r", "TODO").run()?,
+    ///     result => result?,
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The stdout a command produced before it failed.
+    ///
+    /// Populated when output was being captured (e.g. by `output()`) and the
+    /// command exited unsuccessfully; empty otherwise. Useful to show what a long
+    /// build produced before it died.
+    pub fn partial_stdout(&self) -> &[u8] {
+        &self.partial_stdout
+    }
+
+    /// The exit code of the command that failed.
+    ///
+    /// `None` if the command could not be run at all or was terminated by a
+    /// signal. Useful to treat expected codes distinctly, such as `grep`
+    /// exiting with 1 when nothing matched.
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// match cmd!("grep", "TODO", "notes.txt").run() {
+    ///     Ok(()) => println!("found"),
+    ///     Err(e) if e.code() == Some(1) => println!("no match"),
+    ///     Err(e) => return Err(e.into()),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// The exit code to end this process with after the error: the failed
+    /// command's exit code, or 1 if there is none.
+    ///
+    /// This lets a script exit the way the command that broke it did, as a shell
+    /// script with `set -e` would.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// if let Err(e) = cmd!("make", "test").run() {
+    ///     eprintln!("{e}");
+    ///     std::process::exit(e.into_exit_code());
+    /// }
+    /// ```
+    pub fn into_exit_code(self) -> i32 {
+        self.exit_code.filter(|&code| code != 0).unwrap_or(1)
+    }
+
+    /// The stderr of the command that failed.
+    ///
+    /// Populated when stderr was captured, as by `output()` and its variants,
+    /// `run_traced()` and `inherit_stdout_capture_stderr()`; empty when it was
+    /// inherited, as by `run()`, or piped to another command. The beginning of
+    /// it is also part of the `Display` output.
+    pub fn stderr(&self) -> &str {
+        &self.stderr
+    }
+}
+
+impl Error {
+    /// An error of the given kind with no source, exit code or captured output.
+    pub(crate) fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Error {
+            kind,
+            message: message.into(),
+            source: None,
+            partial_stdout: Vec::new(),
+            exit_code: None,
+            stderr: String::new(),
+        }
+    }
+
+    /// An [`ErrorKind::Io`] error caused by `source`.
+    pub(crate) fn io(message: impl Into<String>, source: std::io::Error) -> Self {
+        Error {
+            source: Some(source),
+            ..Error::new(ErrorKind::Io, message)
+        }
+    }
+
+    /// An [`ErrorKind::SpawnFailed`] error, caused by `source` if there is one.
+    pub(crate) fn spawn(message: impl Into<String>, source: Option<std::io::Error>) -> Self {
+        Error {
+            source,
+            ..Error::new(ErrorKind::SpawnFailed, message)
+        }
+    }
+
+    /// An [`ErrorKind::PipeSetup`] error caused by `source`.
+    pub(crate) fn pipe_setup(message: impl Into<String>, source: std::io::Error) -> Self {
+        Error {
+            source: Some(source),
+            ..Error::new(ErrorKind::PipeSetup, message)
+        }
+    }
+
+    /// An [`ErrorKind::NonZeroExit`] error for a command that ended with
+    /// `status`, keeping what it wrote to stdout and stderr.
+    pub(crate) fn exit(
+        status: &std::process::ExitStatus,
+        partial_stdout: Vec<u8>,
+        stderr: String,
+    ) -> Self {
+        Error {
+            partial_stdout,
+            exit_code: status.code(),
+            stderr,
+            ..Error::new(ErrorKind::NonZeroExit, failure_message(status))
+        }
+    }
+
+    /// An [`ErrorKind::InvalidArgument`] error.
+    pub(crate) fn invalid_argument(message: impl Into<String>) -> Self {
+        Error::new(ErrorKind::InvalidArgument, message)
+    }
+
+    /// An [`ErrorKind::Timeout`] error.
+    pub(crate) fn timeout(message: impl Into<String>) -> Self {
+        Error::new(ErrorKind::Timeout, message)
+    }
+
+    /// Name the command that failed at the end of the message.
+    pub(crate) fn with_command(mut self, rendered: &str) -> Self {
+        self.message = format!("{} (command: {})", self.message, rendered);
+        self
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -13,6 +187,18 @@ impl std::fmt::Display for Error {
         if let Some(source) = &self.source {
             write!(f, ": {}", source)?;
         }
+        let stderr = self.stderr.trim();
+        if !stderr.is_empty() {
+            if stderr.len() > DISPLAYED_STDERR_LEN {
+                let mut end = DISPLAYED_STDERR_LEN;
+                while !stderr.is_char_boundary(end) {
+                    end -= 1;
+                }
+                write!(f, ": {}...", &stderr[..end])?;
+            } else {
+                write!(f, ": {}", stderr)?;
+            }
+        }
         Ok(())
     }
 }
@@ -25,9 +211,72 @@ impl std::error::Error for Error {
 
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
-        Error {
-            message: "Command execution failed".to_string(),
-            source: Some(err),
+        Error::io("Command execution failed", err)
+    }
+}
+
+impl From<Error> for std::io::Error {
+    /// Convert into an I/O error, so that `?` works in functions returning
+    /// `std::io::Result`.
+    ///
+    /// The kind of the underlying I/O error is preserved (e.g. `NotFound` for a
+    /// missing program); command failures become [`std::io::ErrorKind::Other`].
+    fn from(err: Error) -> Self {
+        match &err.source {
+            Some(source) => std::io::Error::new(source.kind(), err),
+            None => std::io::Error::other(err),
+        }
+    }
+}
+
+impl From<Error> for std::process::ExitCode {
+    /// Convert into the code to exit with, as [`Error::into_exit_code`] does.
+    ///
+    /// Codes that don't fit the platform-independent range of `ExitCode` become 1.
+    /// This allows `fn main() -> ExitCode` to propagate the failed command's code.
+    fn from(err: Error) -> Self {
+        let code = u8::try_from(err.into_exit_code()).unwrap_or(1);
+        std::process::ExitCode::from(code)
+    }
+}
+
+/// Describe why a command with the given non-successful status failed.
+///
+/// On Unix, termination by a signal is reported with the signal number and
+/// name (e.g. after a segfault or an OOM kill) instead of a missing exit code.
+pub(crate) fn failure_message(status: &std::process::ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return match signal_name(signal) {
+                Some(name) => format!("Command terminated by signal {} ({})", signal, name),
+                None => format!("Command terminated by signal {}", signal),
+            };
         }
     }
+    match status.code() {
+        Some(code) => format!("Command failed with exit code {}", code),
+        None => "Command failed without an exit code".to_string(),
+    }
+}
+
+/// Name of a signal whose number is the same on all common Unix platforms.
+#[cfg(unix)]
+fn signal_name(signal: i32) -> Option<&'static str> {
+    Some(match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        _ => return None,
+    })
 }
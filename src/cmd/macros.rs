@@ -1,12 +1,35 @@
 //! Macros for convenient command creation.
 
 /// Macro to create a new command.
+///
+/// Each argument is added with [`Cmd::arg`](crate::Cmd::arg). An argument
+/// prefixed with `..` is a collection whose items are all added in place, as
+/// with [`Cmd::args`](crate::Cmd::args).
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::cmd;
+///
+/// let files = vec!["a.txt".to_string(), "b.txt".to_string()];
+/// cmd!("tar", "-czf", "backup.tar.gz", ..files, "notes.md").run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
 #[macro_export]
 macro_rules! cmd {
     ($program:expr) => {
         $crate::Cmd::new($program)
     };
-    ($program:expr, $($arg:expr),* $(,)?) => {
-        $crate::Cmd::new($program)$(.arg($arg))*
+    ($program:expr, $($rest:tt)*) => {
+        $crate::cmd!(@args $crate::Cmd::new($program); $($rest)*)
+    };
+    (@args $cmd:expr;) => {
+        $cmd
+    };
+    (@args $cmd:expr; .. $args:expr $(, $($rest:tt)*)?) => {
+        $crate::cmd!(@args $cmd.args($args); $($($rest)*)?)
+    };
+    (@args $cmd:expr; $arg:expr $(, $($rest:tt)*)?) => {
+        $crate::cmd!(@args $cmd.arg($arg); $($($rest)*)?)
     };
 }
@@ -1,18 +1,27 @@
 //! Simple command execution and piping functionality.
 
 mod command;
+#[cfg(feature = "encoding")]
+mod encoding;
 mod error;
 mod macros;
 mod pipeline;
+mod record;
+mod status;
 mod types;
+mod wait;
+mod xargs;
 
 // Re-export public API
-pub use error::Error;
-pub use types::{Cmd, Pipeline, PipelineHandle, PipelineSpawn};
-
-// Internal items for testing
-#[cfg(test)]
-pub(crate) use types::PipeMode;
+pub use error::{Error, ErrorKind};
+pub use record::record;
+pub use status::Status;
+pub use types::{
+    ChildHandle, Cmd, CmdHandle, CmdOutput, PipeMode, Pipeline, PipelineHandle, PipelineReader,
+    PipelineSpawn, RecordedCommand,
+};
+pub use wait::wait_for;
+pub use xargs::Xargs;
 
 #[cfg(test)]
 mod tests;
@@ -1,30 +1,73 @@
 //! Pipeline implementation and execution logic.
 
-use crate::cmd::{error::Error, types::*};
-use crate::style::*;
-use std::io::{BufReader, Read, Write};
-use std::process::{Child, Command as StdCommand, Stdio};
+use crate::cmd::{
+    command::kill_if_running,
+    error::{Error, ErrorKind},
+    record,
+    status::{Status, overall_status},
+    types::*,
+    xargs::Xargs,
+};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::process::{Child, Command as StdCommand, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
+
+/// Size of the buffers moving data in and out of a pipeline, see
+/// [`Pipeline::buffer_size`].
+pub(crate) const DEFAULT_BUFFER_SIZE: usize = 64 * 1024;
 
 impl PipelineHandle {
     /// Wait for all processes in the pipeline to complete.
     pub fn wait(self) -> Result<(), Error> {
-        for mut child in self.children {
-            let status = child.wait().map_err(|e| Error {
-                message: "Failed to wait for child process".to_string(),
-                source: Some(e),
-            })?;
+        let _tees = JoinOnDrop(self.tees);
+        for (mut child, watch) in self.children.into_iter().zip(self.watches) {
+            let status = child
+                .wait()
+                .map_err(|e| Error::io("Failed to wait for child process", e))?;
+            watch.exited(status);
 
             if !status.success() {
-                return Err(Error {
-                    message: format!("Command failed with exit code: {:?}", status.code()),
-                    source: None,
-                });
+                return Err(
+                    Error::exit(&status, Vec::new(), String::new()).with_command(&self.rendered)
+                );
             }
         }
         Ok(())
     }
 
+    /// Wait for all processes and return their exit statuses in pipeline order,
+    /// without treating non-zero exits as errors.
+    pub(crate) fn wait_statuses(self) -> Result<Vec<ExitStatus>, Error> {
+        let _tees = JoinOnDrop(self.tees);
+        self.children
+            .into_iter()
+            .zip(self.watches)
+            .map(|(mut child, watch)| {
+                let status = child
+                    .wait()
+                    .map_err(|e| Error::io("Failed to wait for child process", e))?;
+                watch.exited(status);
+                Ok(status)
+            })
+            .collect()
+    }
+
+    /// Wait for all processes, treating a non-zero exit of the pipeline (see
+    /// [`Pipeline::pipefail`]) as an error unless `allow_failure` is set.
+    pub(crate) fn wait_allowing(self, allow_failure: bool, pipefail: bool) -> Result<(), Error> {
+        let rendered = self.rendered.clone();
+        let status = overall_status(&self.wait_statuses()?, pipefail);
+        if !allow_failure && !status.success() {
+            return Err(Error::exit(&status, Vec::new(), String::new()).with_command(&rendered));
+        }
+        Ok(())
+    }
+
     /// Collect output from the last command in the pipeline.
     /// Note: This only works if the pipeline was spawned with stdout captured.
     pub fn output(self) -> Result<String, Error> {
@@ -35,32 +78,32 @@ impl PipelineHandle {
     /// Collect binary output from the last command in the pipeline.
     /// Note: This only works if the pipeline was spawned with stdout captured.
     pub fn output_bytes(mut self) -> Result<Vec<u8>, Error> {
+        let _tees = JoinOnDrop(std::mem::take(&mut self.tees));
         if let Some(last_child) = self.children.last_mut() {
             if let Some(stdout) = last_child.stdout.take() {
                 use std::io::Read;
                 let mut output = Vec::new();
                 let mut reader = BufReader::new(stdout);
-                reader.read_to_end(&mut output).map_err(|e| Error {
-                    message: "Failed to read stdout".to_string(),
-                    source: Some(e),
-                })?;
+                reader
+                    .read_to_end(&mut output)
+                    .map_err(|e| Error::io("Failed to read stdout", e))?;
 
                 // Wait for the process to complete
-                for mut child in self.children {
-                    child.wait().map_err(|e| Error {
-                        message: "Failed to wait for child process".to_string(),
-                        source: Some(e),
-                    })?;
+                for (mut child, watch) in self.children.into_iter().zip(self.watches) {
+                    let status = child
+                        .wait()
+                        .map_err(|e| Error::io("Failed to wait for child process", e))?;
+                    watch.exited(status);
                 }
 
                 return Ok(output);
             }
         }
 
-        Err(Error {
-            message: "No stdout available to read from".to_string(),
-            source: None,
-        })
+        Err(Error::new(
+            ErrorKind::Io,
+            "No stdout available to read from",
+        ))
     }
 }
 
@@ -83,6 +126,72 @@ impl Pipeline {
         self
     }
 
+    /// Insert a command at `index`, shifting later stages towards the end.
+    ///
+    /// `mode` selects which output of the preceding stage feeds the inserted
+    /// command, as with [`pipe`](Self::pipe), [`pipe_stderr`](Self::pipe_stderr)
+    /// and [`pipe_both`](Self::pipe_both); it is ignored for the first stage.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of stages.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::*;
+    ///
+    /// let verbose = std::env::var_os("VERBOSE").is_some();
+    /// let mut pipeline = cmd!("cat", "access.log").pipe(cmd!("wc", "-l"));
+    /// if !verbose {
+    ///     pipeline = pipeline.insert_at(1, cmd!("grep", "-v", "DEBUG"), PipeMode::Stdout);
+    /// }
+    /// pipeline.run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn insert_at(mut self, index: usize, cmd: Cmd, mode: PipeMode) -> Self {
+        let len = self.connections.len();
+        assert!(
+            index <= len,
+            "insert_at index {} out of range for pipeline of {} stages",
+            index,
+            len
+        );
+        self.connections.insert(index, (cmd, mode));
+        // Tees stay on the stage they were set on, which moved one place later
+        for (stage, _) in &mut self.tees {
+            if *stage >= index {
+                *stage += 1;
+            }
+        }
+        self
+    }
+
+    /// Convert a one-stage pipeline back into a `Cmd`.
+    ///
+    /// Returns `Ok(cmd)` when the pipeline has exactly one command and no
+    /// pipeline-specific state such as input; otherwise the pipeline is returned
+    /// unchanged as `Err(self)`. A pipeline-level `no_echo()` or `allow_failure()`
+    /// is carried over, as is a pipeline-level [`current_dir`](Self::current_dir).
+    // Handing the pipeline back unchanged is the point of the `Err` variant
+    #[allow(clippy::result_large_err)]
+    pub fn into_single_cmd(mut self) -> Result<Cmd, Pipeline> {
+        if self.connections.len() != 1
+            || self.input.is_some()
+            || self.tail_lines.is_some()
+            || !self.tees.is_empty()
+        {
+            return Err(self);
+        }
+        let (mut cmd, _) = self.connections.remove(0);
+        cmd.suppress_echo |= self.suppress_echo;
+        cmd.allow_failure |= self.allow_failure;
+        if cmd.current_dir.is_none() {
+            cmd.current_dir = self.current_dir;
+        }
+        Ok(cmd)
+    }
+
     /// Set binary input data for the pipeline.
     /// Accepts Vec<u8>, &[u8], or other types that can be converted to Vec<u8>.
     pub fn input_bytes(mut self, input: impl AsRef<[u8]>) -> Self {
@@ -97,60 +206,518 @@ impl Pipeline {
         self
     }
 
-    /// Set text input for the pipeline (deprecated: use spawn_with_io for more control).
-    /// This is kept for backward compatibility but users should prefer the spawn_with_* methods.
-    pub fn input(mut self, input: impl AsRef<str>) -> Self {
-        self.input = Some(CmdInput::Bytes(input.as_ref().as_bytes().to_vec()));
+    /// Set input for the pipeline from text or raw bytes.
+    ///
+    /// Accepts anything viewable as bytes, such as `&str`, `String` or
+    /// `&Vec<u8>`, so binary data is passed through unchanged.
+    pub fn input(mut self, input: impl AsRef<[u8]>) -> Self {
+        self.input = Some(CmdInput::Bytes(input.as_ref().to_vec()));
         self
     }
 
-    /// Set input from a Reader (deprecated: use spawn_with_stdin for more control).
-    /// Note: This will cause an error with output() - use spawn_with_* methods instead.
+    /// Set input from a Reader.
+    /// The reader is streamed into the first command's stdin from a separate thread,
+    /// for single-command and multi-command pipelines alike.
     pub fn input_reader<R: Read + Send + 'static>(mut self, reader: R) -> Self {
         self.input = Some(CmdInput::Reader(Box::new(reader)));
         self
     }
 
-    /// Set input from a Reader with automatic buffering (deprecated: use spawn_with_stdin for more control).
-    /// Note: This will cause an error with output() - use spawn_with_* methods instead.
+    /// Set input from a Reader with automatic buffering.
+    /// More efficient for large files or slow readers.
     pub fn input_buffered<R: Read + Send + 'static>(self, reader: R) -> Self {
         self.input_reader(BufReader::new(reader))
     }
 
+    /// Use a file as the first stage's stdin, as with `cmd < path | ...` in a shell.
+    ///
+    /// The file is opened when the pipeline runs and handed to the command
+    /// directly, so it is never read into memory. Like the other input methods,
+    /// it replaces any input set before; the last one wins. The redirection is
+    /// shown when the pipeline is echoed.
+    pub fn stdin_from_file(mut self, path: impl AsRef<Path>) -> Self {
+        self.input = Some(CmdInput::File(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Stream a file into the first stage without reading it into memory.
+    ///
+    /// Same as [`stdin_from_file`](Self::stdin_from_file), named to match the
+    /// other input methods. Prefer it over passing the contents of a large file
+    /// to [`input`](Self::input).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let errors = cmd!("grep", "ERROR")
+    ///     .pipe(cmd!("wc", "-l"))
+    ///     .input_file("/var/log/app.log")
+    ///     .output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn input_file(self, path: impl AsRef<Path>) -> Self {
+        self.stdin_from_file(path)
+    }
+
+    /// Give the first stage an empty stdin, as with `cmd < /dev/null | ...` in a shell.
+    ///
+    /// The first stage of `run()`, `output()` and similar methods already gets an
+    /// empty stdin when no input is set; this makes it explicit, replaces any
+    /// input set before, and also applies to the `spawn_with_*` methods, which
+    /// otherwise pipe stdin. The redirection is shown when the pipeline is echoed.
+    pub fn stdin_null(mut self) -> Self {
+        self.input = Some(CmdInput::Null);
+        self
+    }
+
+    /// Feed the first stage from this process's stdin, discarding any input set before.
+    ///
+    /// This is like `stdin | pipeline` in a shell, e.g. to filter data piped into
+    /// the script or to let a command prompt the user. Without it, the first stage
+    /// of `run()`, `output()` and similar methods gets an empty stdin unless input
+    /// is set with [`input`](Self::input) or a related method, so commands such as
+    /// `cat` cannot block on the terminal or consume the script's own input.
+    pub fn stdin_inherit(mut self) -> Self {
+        self.input = Some(CmdInput::Inherit);
+        self
+    }
+
     /// Run without echoing the pipeline.
     pub fn no_echo(mut self) -> Self {
         self.suppress_echo = true;
         self
     }
 
+    /// Don't treat a non-zero exit of any stage as an error.
+    ///
+    /// See [`Cmd::allow_failure`].
+    pub fn allow_failure(mut self) -> Self {
+        self.allow_failure = true;
+        self
+    }
+
+    /// Keep only the last `n` lines of the captured output.
+    ///
+    /// With [`output`](Self::output) and the other methods that capture stdout
+    /// through it, the output is read line by line and older lines are dropped
+    /// as new ones arrive, so at most `n` lines are held in memory even if the
+    /// command writes gigabytes.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let recent = cmd!("journalctl", "-u", "nginx").tail_lines(100).output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn tail_lines(mut self, n: usize) -> Self {
+        self.tail_lines = Some(n);
+        self
+    }
+
+    /// Copy the output of the last command so far to `writer`, as with
+    /// `cmd | tee file | next` in a shell.
+    ///
+    /// A command must be piped after this one: what this command sends to it
+    /// is also written to `writer` from a separate thread, so the output can be
+    /// saved and processed further without running the command twice. The
+    /// copying is done when the pipeline has finished. To copy the output of
+    /// the last command, use [`tee`](Self::tee) instead.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let file = std::fs::File::create("generated.txt")?;
+    /// let count = cmd!("generate").tee_to(file).pipe(cmd!("wc", "-l")).output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn tee_to<W: Write + Send + 'static>(mut self, writer: W) -> Self {
+        let stage = self.connections.len().saturating_sub(1);
+        self.tees.push((stage, TeeWriter(Box::new(writer))));
+        self
+    }
+
+    /// Set the working directory of every command that doesn't have its own.
+    ///
+    /// Commands given a directory with [`Cmd::current_dir`] keep it, also if
+    /// they are piped after this call. The directory is shown once at the
+    /// start of the echoed pipeline.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let count = cmd!("ls").pipe(cmd!("wc", "-l")).current_dir("/var/log").output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the size of the buffers used to move data in and out of the pipeline.
+    ///
+    /// The buffers are used when feeding input to the first command, reading
+    /// the output of the last one and copying output with [`tee_to`](Self::tee_to).
+    /// The default of 64 KiB suits most uses; a larger buffer can speed up
+    /// pipelines moving gigabytes of data. Sizes below one byte are rounded up.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let backup = std::fs::File::create("backup.sql.gz")?;
+    /// cmd!("pg_dump", "mydb")
+    ///     .pipe(cmd!("gzip"))
+    ///     .buffer_size(1024 * 1024)
+    ///     .stream_to(backup)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn buffer_size(mut self, size: usize) -> Self {
+        self.buffer_size = size.max(1);
+        self
+    }
+
+    /// Choose whether a failing stage other than the last one fails the pipeline.
+    ///
+    /// By default (`true`) the pipeline fails if any stage exits with a non-zero
+    /// status, like bash with `set -o pipefail`, and the first failing stage
+    /// determines the reported status. With `false` only the last stage's status
+    /// matters, as in a plain shell pipeline `cmd1 | cmd2`; this is useful when an
+    /// early stage is expected to fail, e.g. with `SIGPIPE` after `head` exits.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// // grep exits with 1 when nothing matches, but only the count matters
+    /// let count = cmd!("grep", "TODO", "notes.txt")
+    ///     .pipe(cmd!("wc", "-l"))
+    ///     .pipefail(false)
+    ///     .output()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn pipefail(mut self, enabled: bool) -> Self {
+        self.pipefail = enabled;
+        self
+    }
+
+    /// Let the last command write stdout and stderr directly to this process's
+    /// stdout and stderr instead of to pipes.
+    ///
+    /// See [`Cmd::inherit`].
+    pub fn inherit(mut self) -> Self {
+        if let Some((last, _)) = self.connections.last_mut() {
+            last.inherit = true;
+        }
+        self
+    }
+
     /// Run the pipeline.
+    ///
+    /// The last command's stdout and stderr are not captured but inherited, so
+    /// its output appears live on the terminal, as does the stderr of earlier
+    /// commands piping only stdout. A non-zero exit of any command is an error.
     pub fn run(self) -> Result<(), Error> {
         self.execute_internal(false).map(|_| ())
     }
 
-    /// Run the pipeline and return the output as a string.
+    /// Run the pipeline and return its exit status.
+    ///
+    /// Unlike [`run`](Self::run), a non-zero exit is not an error: the status of
+    /// the first stage that failed (or of the last stage if all succeeded, or
+    /// only the last stage with [`pipefail(false)`](Self::pipefail)) is returned
+    /// instead. Failing to spawn a command is still an error.
+    pub fn status(mut self) -> Result<Status, Error> {
+        self.allow_failure = true;
+        let pipefail = self.pipefail;
+        let (_, statuses) = self.execute(false)?;
+        Ok(Status::from(overall_status(&statuses, pipefail)))
+    }
+
     /// Get binary output from the pipeline.
+    ///
+    /// The bytes are returned exactly as written by the last command, so this is
+    /// the method to use for output that may not be UTF-8, such as file names
+    /// listed by `find -print0`.
     pub fn output_bytes(self) -> Result<Vec<u8>, Error> {
         self.execute_internal(true)
     }
 
+    /// Get binary output from the selected stream(s) of the last command.
+    ///
+    /// Unlike [`output_bytes`](Self::output_bytes), which always captures stdout,
+    /// this lets the final stream be chosen independently of how the stages are
+    /// connected, e.g. when a final filter reports its results on stderr. With
+    /// [`PipeMode::Both`] the streams are merged in arrival order.
+    pub fn output_bytes_of(mut self, which: PipeMode) -> Result<Vec<u8>, Error> {
+        let echo = !self.suppress_echo;
+        let allow_failure = self.allow_failure;
+        let pipefail = self.pipefail;
+        let buffer_size = self.buffer_size;
+        let input = self.take_fed_input();
+        let spawn = self.spawn_stages(input.is_some())?;
+        let input_handle = feed_input(input, spawn.stdin, buffer_size);
+
+        let collected = Arc::new(Mutex::new(Vec::new()));
+        let sink = |keep: bool| keep.then(|| Arc::clone(&collected));
+        let stdout_handle = spawn.stdout.map(|stdout| {
+            collect_stream(
+                stdout,
+                sink(matches!(which, PipeMode::Stdout | PipeMode::Both)),
+                buffer_size,
+            )
+        });
+        let stderr_handle = spawn.stderr.map(|stderr| {
+            collect_stream(
+                stderr,
+                sink(matches!(which, PipeMode::Stderr | PipeMode::Both)),
+                buffer_size,
+            )
+        });
+
+        // A failed read is reported only after the commands are waited for
+        let mut read = Ok(());
+        for handle in [stdout_handle, stderr_handle].into_iter().flatten() {
+            let result = handle
+                .join()
+                .unwrap_or_else(|_| Err(std::io::Error::other("output reader panicked")));
+            read = read.and(result);
+        }
+
+        if let Some(handle) = input_handle {
+            let _ = handle.join();
+        }
+
+        let waited = spawn.handle.wait_allowing(allow_failure, pipefail);
+        read.map_err(|e| Error::io("Failed to read pipeline output", e))?;
+        waited?;
+        let output = std::mem::take(&mut *collected.lock().unwrap_or_else(|e| e.into_inner()));
+        if echo {
+            echo_output(&output);
+        }
+        Ok(output)
+    }
+
     /// Get text output from the pipeline.
+    ///
+    /// The output is not trimmed; see [`output_raw`](Self::output_raw). Invalid
+    /// UTF-8 is replaced with `U+FFFD`; use [`output_bytes`](Self::output_bytes)
+    /// to get the raw bytes.
     pub fn output(self) -> Result<String, Error> {
         let bytes = self.output_bytes()?;
         Ok(String::from_utf8_lossy(&bytes).to_string())
     }
 
+    /// Get text output from the pipeline together with its exit status.
+    ///
+    /// Unlike [`output`](Self::output), a non-zero exit is not an error: the
+    /// captured stdout is returned along with the status of the first stage that
+    /// failed (or of the last stage if all succeeded, or only the last stage with
+    /// [`pipefail(false)`](Self::pipefail)). Failing to spawn a command is still
+    /// an error.
+    pub fn output_with_status(mut self) -> Result<(String, Status), Error> {
+        self.allow_failure = true;
+        let pipefail = self.pipefail;
+        let (bytes, statuses) = self.execute(true)?;
+        Ok((
+            String::from_utf8_lossy(&bytes).to_string(),
+            Status::from(overall_status(&statuses, pipefail)),
+        ))
+    }
+
+    /// Get binary output from the pipeline together with the exit status of each
+    /// stage, in pipeline order.
+    ///
+    /// A non-zero exit is not an error, so it can be seen which stage failed.
+    /// Failing to spawn a command is still an error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// let (output, statuses) = cmd!("cat", "access.log")
+    ///     .pipe(cmd!("grep", "ERROR"))
+    ///     .pipe(cmd!("sort"))
+    ///     .output_bytes_split()?;
+    /// if let Some(index) = statuses.iter().position(|status| !status.success()) {
+    ///     eprintln!("stage {index} failed");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn output_bytes_split(mut self) -> Result<(Vec<u8>, Vec<Status>), Error> {
+        self.allow_failure = true;
+        let (bytes, statuses) = self.execute(true)?;
+        Ok((bytes, statuses.into_iter().map(Status::from).collect()))
+    }
+
+    /// Get text output from the pipeline exactly as it was written.
+    ///
+    /// No whitespace is trimmed and line endings are left untouched, so trailing
+    /// newlines are preserved, e.g. when reconstructing exact file contents. The
+    /// only change is that invalid UTF-8 is replaced with `U+FFFD`; use
+    /// [`output_bytes`](Self::output_bytes) to avoid that.
+    pub fn output_raw(self) -> Result<String, Error> {
+        self.output()
+    }
+
+    /// Get text output from the pipeline, decoded from the encoding named by `label`.
+    ///
+    /// Supported labels are `utf-8`, `latin1` (`iso-8859-1`) and `windows-1252`
+    /// (`cp1252`), matched case-insensitively. Use this for legacy tools whose
+    /// output is not UTF-8, which [`output`](Self::output) would mangle.
+    ///
+    /// Available with the `encoding` feature.
+    #[cfg(feature = "encoding")]
+    pub fn output_with_encoding(self, label: &str) -> Result<String, Error> {
+        // Reject an unknown label before running anything
+        crate::cmd::encoding::decode(&[], label)?;
+        let bytes = self.output_bytes()?;
+        crate::cmd::encoding::decode(&bytes, label)
+    }
+
+    /// Get text output from the pipeline with `\r\n` line endings converted to `\n`.
+    ///
+    /// Nothing else is modified; trailing newlines are preserved.
+    pub fn output_normalized(self) -> Result<String, Error> {
+        Ok(self.output()?.replace("\r\n", "\n"))
+    }
+
+    /// Get stdout and stderr of the last command as separate strings.
+    ///
+    /// Unlike [`PipeMode::Both`], the streams are not merged. Both are read
+    /// concurrently, so large output on either one cannot block the command.
+    pub fn output_split(self) -> Result<(String, String), Error> {
+        let allow_failure = self.allow_failure;
+        let (output, rendered) = self.run_traced()?;
+        let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        if !allow_failure && !output.status.success() {
+            return Err(Error::exit(&output.status, output.stdout, stderr).with_command(&rendered));
+        }
+        Ok((String::from_utf8_lossy(&output.stdout).into_owned(), stderr))
+    }
+
+    /// Run the pipeline, capturing its output, and return it together with the
+    /// plain-text command line as it is echoed.
+    ///
+    /// Both stdout and stderr of the last command are captured. A non-zero exit is
+    /// not an error here: it is reported in [`CmdOutput::status`] (the first failing
+    /// stage, or the last stage if all succeeded, or only the last stage with
+    /// [`pipefail(false)`](Self::pipefail)), which makes this convenient for
+    /// snapshot-testing both the behavior and the trace of a command.
+    pub fn run_traced(mut self) -> Result<(CmdOutput, String), Error> {
+        let rendered = self.render(false);
+        let pipefail = self.pipefail;
+        let buffer_size = self.buffer_size;
+        let input = self.take_fed_input();
+        let spawn = self.spawn_stages(input.is_some())?;
+
+        let input_handle = feed_input(input, spawn.stdin, buffer_size);
+        let stderr_handle = spawn.stderr.map(|mut stderr| {
+            thread::spawn(move || {
+                let mut buf = Vec::new();
+                stderr.read_to_end(&mut buf).map(|_| buf)
+            })
+        });
+
+        // Read errors are reported only after the commands are waited for
+        let mut stdout = Vec::new();
+        let stdout_read = match spawn.stdout {
+            Some(out) => BufReader::new(out).read_to_end(&mut stdout).map(|_| ()),
+            None => Ok(()),
+        };
+
+        let stderr_read = match stderr_handle {
+            Some(handle) => handle
+                .join()
+                .unwrap_or_else(|_| Err(std::io::Error::other("stderr reader panicked"))),
+            None => Ok(Vec::new()),
+        };
+
+        if let Some(handle) = input_handle {
+            let _ = handle.join();
+        }
+
+        let statuses = spawn.handle.wait_statuses();
+        stdout_read.map_err(|e| Error::io("Failed to read stdout", e))?;
+        let stderr = stderr_read.map_err(|e| Error::io("Failed to read stderr", e))?;
+        let status = overall_status(&statuses?, pipefail);
+
+        Ok((
+            CmdOutput {
+                status,
+                stdout,
+                stderr,
+            },
+            rendered,
+        ))
+    }
+
     /// Spawn pipeline with full I/O access.
     /// User is responsible for managing stdin, stdout, and stderr in separate threads.
     pub fn spawn_with_io(self) -> Result<PipelineSpawn, Error> {
+        self.spawn_stages(true)
+    }
+
+    /// Destination of an output stream of the last command.
+    fn output_stdio(cmd: &Cmd) -> Stdio {
+        if cmd.inherit {
+            Stdio::inherit()
+        } else {
+            Stdio::piped()
+        }
+    }
+
+    /// Spawn all stages. Unless the input says otherwise, the first stage's stdin
+    /// is piped if `pipe_stdin` is set and empty otherwise.
+    fn spawn_stages(mut self, pipe_stdin: bool) -> Result<PipelineSpawn, Error> {
         if !self.suppress_echo {
             self.echo_pipeline();
         }
+        let rendered = self.render(false);
+
+        if let Some(dir) = &self.current_dir {
+            for (cmd, _) in &mut self.connections {
+                if cmd.current_dir.is_none() {
+                    cmd.current_dir = Some(dir.clone());
+                }
+            }
+        }
+
+        let skipped = record::skip_execution(&self);
+        if !skipped {
+            // Reject invalid commands before any stage is spawned
+            for (cmd, _) in &mut self.connections {
+                cmd.prepare()?;
+            }
+            if self
+                .tees
+                .iter()
+                .any(|(stage, _)| stage + 1 >= self.connections.len())
+            {
+                return Err(Error::invalid_argument(
+                    "tee_to() needs a command piped after it; use tee() to copy the final output"
+                        .to_string(),
+                ));
+            }
+        }
 
-        if self.connections.is_empty() {
+        if self.connections.is_empty() || skipped {
             return Ok(PipelineSpawn {
                 handle: PipelineHandle {
                     children: Vec::new(),
+                    watches: Vec::new(),
+                    tees: Vec::new(),
+                    rendered,
                 },
                 stdin: None,
                 stdout: None,
@@ -158,20 +725,27 @@ impl Pipeline {
             });
         }
 
+        // First command: from a file, inherited, piped for input, or empty
+        let mut stdin_source = Some(match self.input.take() {
+            Some(CmdInput::File(path)) => open_input_file(&path)?,
+            Some(CmdInput::Null) => Stdio::null(),
+            Some(CmdInput::Inherit) => Stdio::inherit(),
+            _ if pipe_stdin => Stdio::piped(),
+            _ => Stdio::null(),
+        });
+
         // For single command, handle it specially
         if self.connections.len() == 1 {
             let cmd = self.connections.into_iter().next().unwrap().0;
             let mut std_cmd = Self::build_std_command_static(&cmd);
 
-            // Set up I/O - always enable stdin for compatibility
-            std_cmd.stdin(Stdio::piped());
-            std_cmd.stdout(Stdio::piped());
-            std_cmd.stderr(Stdio::piped());
+            std_cmd.stdin(stdin_source.take().unwrap_or_else(Stdio::null));
+            std_cmd.stdout(Self::output_stdio(&cmd));
+            std_cmd.stderr(Self::output_stdio(&cmd));
+            cmd.apply_redirects(&mut std_cmd)?;
 
-            let mut child = std_cmd.spawn().map_err(|e| Error {
-                message: format!("Failed to spawn command: {}", cmd.program.to_string_lossy()),
-                source: Some(e),
-            })?;
+            let mut child = std_cmd.spawn().map_err(|e| cmd.spawn_error(e))?;
+            let watch = cmd.observers.spawned(&child);
 
             let stdin = child.stdin.take();
             let stdout = child.stdout.take();
@@ -182,6 +756,9 @@ impl Pipeline {
             return Ok(PipelineSpawn {
                 handle: PipelineHandle {
                     children: vec![child],
+                    watches: vec![watch],
+                    tees: Vec::new(),
+                    rendered,
                 },
                 stdin,
                 stdout,
@@ -191,6 +768,9 @@ impl Pipeline {
 
         // Multi-command pipeline
         let mut children: Vec<Child> = Vec::new();
+        let mut watches = Vec::new();
+        let mut tee_writers = std::mem::take(&mut self.tees);
+        let mut tees = Vec::new();
         let mut prev_reader: Option<std::io::PipeReader> = None;
         let mut first_stdin = None;
         let mut last_stdout = None;
@@ -202,8 +782,7 @@ impl Pipeline {
 
             // Set up stdin
             if i == 0 {
-                // First command: set up for potential input
-                cmd.stdin(Stdio::piped());
+                cmd.stdin(stdin_source.take().unwrap_or_else(Stdio::null));
             } else {
                 // Subsequent commands: use previous command's output
                 if let Some(reader) = prev_reader.take() {
@@ -214,52 +793,53 @@ impl Pipeline {
             // Set up stdout and stderr
             let is_last = i == self.connections.len() - 1;
             if is_last {
-                // Last command: capture both stdout and stderr
-                cmd.stdout(Stdio::piped());
-                cmd.stderr(Stdio::piped());
+                // Last command: capture both stdout and stderr unless inherited
+                cmd.stdout(Self::output_stdio(cmd_def));
+                cmd.stderr(Self::output_stdio(cmd_def));
             } else {
                 // Intermediate commands: pipe to next command
                 let next_pipe_mode = self.connections[i + 1].1;
                 match next_pipe_mode {
                     PipeMode::Stdout => {
-                        let (reader, writer) = std::io::pipe().map_err(|e| Error {
-                            message: "Failed to create stdout pipe".to_string(),
-                            source: Some(e),
-                        })?;
+                        let (reader, writer) = std::io::pipe()
+                            .map_err(|e| Error::pipe_setup("Failed to create stdout pipe", e))?;
                         cmd.stdout(Stdio::from(writer));
                         prev_reader = Some(reader);
                     }
                     PipeMode::Stderr => {
-                        let (reader, writer) = std::io::pipe().map_err(|e| Error {
-                            message: "Failed to create stderr pipe".to_string(),
-                            source: Some(e),
-                        })?;
+                        let (reader, writer) = std::io::pipe()
+                            .map_err(|e| Error::pipe_setup("Failed to create stderr pipe", e))?;
                         cmd.stderr(Stdio::from(writer));
                         prev_reader = Some(reader);
                     }
                     PipeMode::Both => {
-                        let (reader, writer) = std::io::pipe().map_err(|e| Error {
-                            message: "Failed to create combined pipe".to_string(),
-                            source: Some(e),
-                        })?;
-                        let writer_clone = writer.try_clone().map_err(|e| Error {
-                            message: "Failed to clone pipe writer".to_string(),
-                            source: Some(e),
-                        })?;
+                        let (reader, writer) = std::io::pipe()
+                            .map_err(|e| Error::pipe_setup("Failed to create combined pipe", e))?;
+                        let writer_clone = writer
+                            .try_clone()
+                            .map_err(|e| Error::pipe_setup("Failed to clone pipe writer", e))?;
                         cmd.stdout(Stdio::from(writer));
                         cmd.stderr(Stdio::from(writer_clone));
                         prev_reader = Some(reader);
                     }
                 }
+
+                // Insert a copying thread between this stage and the next for each tee
+                while let Some(pos) = tee_writers.iter().position(|(stage, _)| *stage == i) {
+                    let (_, tee) = tee_writers.swap_remove(pos);
+                    let (reader, writer) = std::io::pipe()
+                        .map_err(|e| Error::pipe_setup("Failed to create tee pipe", e))?;
+                    if let Some(source) = prev_reader.replace(reader) {
+                        tees.push(spawn_tee(source, writer, tee, self.buffer_size));
+                    }
+                }
             }
 
-            let mut child = cmd.spawn().map_err(|e| Error {
-                message: format!(
-                    "Failed to spawn command: {}",
-                    cmd_def.program.to_string_lossy()
-                ),
-                source: Some(e),
-            })?;
+            // Per-stage redirections take precedence, as with `cmd > file | next`
+            cmd_def.apply_redirects(&mut cmd)?;
+
+            let mut child = cmd.spawn().map_err(|e| cmd_def.spawn_error(e))?;
+            watches.push(cmd_def.observers.spawned(&child));
 
             // Capture I/O handles
             if i == 0 {
@@ -276,7 +856,12 @@ impl Pipeline {
         // For pipelines, input handling is now user's responsibility via spawn API
 
         Ok(PipelineSpawn {
-            handle: PipelineHandle { children },
+            handle: PipelineHandle {
+                children,
+                watches,
+                tees,
+                rendered,
+            },
             stdin: first_stdin,
             stdout: last_stdout,
             stderr: last_stderr,
@@ -325,37 +910,31 @@ impl Pipeline {
     /// Run the pipeline and stream output to a Writer.
     /// This is more memory-efficient for large outputs.
     pub fn stream_to<W: Write>(mut self, mut writer: W) -> Result<(), Error> {
+        let allow_failure = self.allow_failure;
+        let pipefail = self.pipefail;
+        let buffer_size = self.buffer_size;
         // Extract input before spawning
-        let input = self.input.take();
-        let spawn = self.spawn_with_io()?;
+        let input = self.take_fed_input();
+        let spawn = self.spawn_stages(input.is_some())?;
 
         // Handle input in separate thread if provided
-        let input_handle = match input {
-            Some(CmdInput::Bytes(bytes)) => spawn.stdin.map(|mut stdin| {
-                thread::spawn(move || {
-                    use std::io::Write;
-                    let _ = stdin.write_all(&bytes);
-                    drop(stdin);
-                })
-            }),
-            Some(CmdInput::Reader(mut reader)) => spawn.stdin.map(|stdin| {
-                thread::spawn(move || {
-                    use std::io::copy;
-                    let mut stdin = stdin;
-                    let _ = copy(&mut reader, &mut stdin);
-                    drop(stdin);
-                })
-            }),
-            None => None,
-        };
+        let input_handle = feed_input(input, spawn.stdin, buffer_size);
+        let stderr_handle = spawn
+            .stderr
+            .map(|stderr| collect_stream(stderr, None, buffer_size));
 
         // Handle output in current thread
         if let Some(stdout) = spawn.stdout {
             use std::io::copy;
-            copy(&mut BufReader::new(stdout), &mut writer).map_err(|e| Error {
-                message: "Failed to copy pipeline output to writer".to_string(),
-                source: Some(e),
-            })?;
+            copy(
+                &mut BufReader::with_capacity(buffer_size, stdout),
+                &mut writer,
+            )
+            .map_err(|e| Error::io("Failed to copy pipeline output to writer", e))?;
+        }
+
+        if let Some(handle) = stderr_handle {
+            let _ = handle.join();
         }
 
         // Wait for input thread to complete if exists
@@ -363,7 +942,189 @@ impl Pipeline {
             let _ = handle.join();
         }
 
-        spawn.handle.wait()
+        spawn.handle.wait_allowing(allow_failure, pipefail)
+    }
+
+    /// Run the pipeline, streaming its output to a Writer while also returning it.
+    ///
+    /// Like [`stream_to`](Self::stream_to), the output is written as it arrives,
+    /// e.g. to show progress on the terminal, but it is also collected and
+    /// returned as with [`output`](Self::output). If the pipeline fails, the
+    /// output collected so far is available from
+    /// [`Error::partial_stdout`](crate::Error::partial_stdout).
+    ///
+    /// ```no_run
+    /// use scriptify::*;
+    ///
+    /// let log = cmd!("cargo", "test").tee(std::io::stdout())?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn tee<W: Write>(self, writer: W) -> Result<String, Error> {
+        let mut tee = Tee {
+            writer,
+            captured: Vec::new(),
+        };
+        match self.stream_to(&mut tee) {
+            Ok(()) => Ok(String::from_utf8_lossy(&tee.captured).into_owned()),
+            Err(mut e) => {
+                e.partial_stdout = tee.captured;
+                Err(e)
+            }
+        }
+    }
+
+    /// Pass the output of the pipeline as arguments to `cmd`, like `xargs`.
+    ///
+    /// The pipeline runs to completion first; its output is then split into
+    /// items that are appended to `cmd` when the returned [`Xargs`] is run.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    ///
+    /// // Remove all backup files, 100 at a time
+    /// cmd!("find", ".", "-name", "*.bak", "-print0")
+    ///     .xargs(cmd!("rm", "--"))
+    ///     .null()
+    ///     .batch(100)
+    ///     .run()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn xargs(self, cmd: Cmd) -> Xargs {
+        Xargs::new(self, cmd)
+    }
+
+    /// Run the pipeline, calling `f` with each line of its output as it arrives.
+    ///
+    /// Lines are passed without their line ending; invalid UTF-8 is replaced
+    /// with `U+FFFD`. Returning [`ControlFlow::Break`] from `f` stops reading and
+    /// kills the commands, which is the way to stop following an endless
+    /// command such as `tail -f`; this is not an error. Otherwise the pipeline
+    /// runs to the end and a non-zero exit is an error as with [`run`](Self::run).
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::cmd;
+    /// use std::ops::ControlFlow;
+    ///
+    /// cmd!("tail", "-f", "server.log").for_each_line(|line| {
+    ///     println!("log: {line}");
+    ///     if line.contains("ready") {
+    ///         ControlFlow::Break(())
+    ///     } else {
+    ///         ControlFlow::Continue(())
+    ///     }
+    /// })?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn for_each_line<F>(mut self, mut f: F) -> Result<(), Error>
+    where
+        F: FnMut(&str) -> ControlFlow<()>,
+    {
+        let allow_failure = self.allow_failure;
+        let pipefail = self.pipefail;
+        let buffer_size = self.buffer_size;
+        let input = self.take_fed_input();
+        let spawn = self.spawn_stages(input.is_some())?;
+
+        let input_handle = feed_input(input, spawn.stdin, buffer_size);
+        let stderr_handle = spawn
+            .stderr
+            .map(|stderr| collect_stream(stderr, None, buffer_size));
+        let mut handle = spawn.handle;
+
+        // Whether reading stopped before the end of the output, or failed
+        let mut stopped = Ok(false);
+        if let Some(stdout) = spawn.stdout {
+            let mut reader = BufReader::with_capacity(buffer_size, stdout);
+            let mut buf = Vec::new();
+            loop {
+                buf.clear();
+                match reader.read_until(b'\n', &mut buf) {
+                    Ok(0) => break,
+                    Ok(_) => {}
+                    Err(e) => {
+                        stopped = Err(e);
+                        break;
+                    }
+                }
+
+                let line = buf.strip_suffix(b"\n").unwrap_or(&buf);
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                if f(&String::from_utf8_lossy(line)).is_break() {
+                    stopped = Ok(true);
+                    break;
+                }
+            }
+        }
+
+        if !matches!(stopped, Ok(false)) {
+            // The commands may never finish on their own
+            for child in &mut handle.children {
+                let _ = child.kill();
+            }
+            let _ = handle.wait_statuses();
+            return stopped
+                .map(|_| ())
+                .map_err(|e| Error::io("Failed to read stdout", e));
+        }
+
+        if let Some(handle) = stderr_handle {
+            let _ = handle.join();
+        }
+        if let Some(handle) = input_handle {
+            let _ = handle.join();
+        }
+
+        handle.wait_allowing(allow_failure, pipefail)
+    }
+
+    /// Start the pipeline and return a reader over its output.
+    ///
+    /// Unlike [`output`](Self::output), nothing is collected: the output is
+    /// read as the pipeline produces it, so a long-running pipeline can be
+    /// consumed lazily, e.g. line by line through a [`BufReader`]. Stderr is
+    /// collected in the background to be reported if the pipeline fails; call
+    /// [`PipelineReader::wait`] when done reading, or drop the reader to kill
+    /// the commands instead.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use scriptify::*;
+    ///
+    /// let mut reader = cmd!("journalctl", "-f").pipe(cmd!("grep", "error")).reader()?;
+    /// for line in BufReader::new(&mut reader).lines().take(10) {
+    ///     println!("{}", line?);
+    /// }
+    /// // `journalctl -f` never ends, so stop it instead of waiting
+    /// drop(reader);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn reader(mut self) -> Result<PipelineReader, Error> {
+        let allow_failure = self.allow_failure;
+        let pipefail = self.pipefail;
+        let buffer_size = self.buffer_size;
+        let input = self.take_fed_input();
+        let spawn = self.spawn_stages(input.is_some())?;
+
+        let input_handle = feed_input(input, spawn.stdin, buffer_size);
+        let stderr = Arc::new(Mutex::new(Vec::new()));
+        let stderr_reader = spawn
+            .stderr
+            .map(|pipe| collect_stream(pipe, Some(Arc::clone(&stderr)), buffer_size));
+
+        Ok(PipelineReader {
+            stdout: spawn.stdout,
+            handle: Some(spawn.handle),
+            input: input_handle,
+            stderr_reader,
+            stderr,
+            allow_failure,
+            pipefail,
+        })
     }
 
     /// Run the pipeline with both input Reader and output Writer.
@@ -373,106 +1134,200 @@ impl Pipeline {
         mut reader: R,
         mut writer: W,
     ) -> Result<(), Error> {
+        let allow_failure = self.allow_failure;
+        let pipefail = self.pipefail;
+        let buffer_size = self.buffer_size;
         let spawn = self.spawn_with_io()?;
 
         // Handle input in separate thread
         if let Some(mut stdin) = spawn.stdin {
             thread::spawn(move || {
-                use std::io::copy;
-                let _ = copy(&mut reader, &mut stdin);
+                let mut reader = BufReader::with_capacity(buffer_size, &mut reader);
+                let _ = std::io::copy(&mut reader, &mut stdin);
             });
         }
+        let stderr_handle = spawn
+            .stderr
+            .map(|stderr| collect_stream(stderr, None, buffer_size));
 
         // Handle output in current thread
         if let Some(stdout) = spawn.stdout {
             use std::io::copy;
-            copy(&mut BufReader::new(stdout), &mut writer).map_err(|e| Error {
-                message: "Failed to copy pipeline output to writer".to_string(),
-                source: Some(e),
-            })?;
+            copy(
+                &mut BufReader::with_capacity(buffer_size, stdout),
+                &mut writer,
+            )
+            .map_err(|e| Error::io("Failed to copy pipeline output to writer", e))?;
+        }
+
+        if let Some(handle) = stderr_handle {
+            let _ = handle.join();
         }
 
-        spawn.handle.wait()
+        spawn.handle.wait_allowing(allow_failure, pipefail)
     }
 
-    fn execute_internal(mut self, capture_output: bool) -> Result<Vec<u8>, Error> {
+    /// Take the input that the caller writes to stdin itself; a file, the null
+    /// device or an inherited stdin is left in place for
+    /// [`spawn_stages`](Self::spawn_stages) to hand to the first stage.
+    fn take_fed_input(&mut self) -> Option<CmdInput> {
+        match self.input {
+            Some(CmdInput::File(_) | CmdInput::Null | CmdInput::Inherit) => None,
+            _ => self.input.take(),
+        }
+    }
+
+    fn execute_internal(self, capture_output: bool) -> Result<Vec<u8>, Error> {
+        self.execute(capture_output).map(|(output, _)| output)
+    }
+
+    /// Run the pipeline, returning the captured stdout (if requested) together
+    /// with the exit status of each stage.
+    fn execute(self, capture_output: bool) -> Result<(Vec<u8>, Vec<ExitStatus>), Error> {
         // Echo pipeline first if not suppressed
         let original_suppress = self.suppress_echo;
         if !original_suppress {
             self.echo_pipeline();
         }
 
-        // Extract input before moving self
-        let input = self.input.take();
+        // Report the elapsed time once the pipeline finishes, whether it failed or not
+        let timed = !original_suppress
+            && (crate::timing::is_timing() || crate::verbosity() >= crate::Verbosity::Debug)
+            && !record::is_recording()
+            && !crate::dry_run::is_dry_run();
+        let result = if timed {
+            let rendered = self.render(true);
+            let started = Instant::now();
+            let result = self.execute_stages(capture_output);
+            echo_elapsed(&rendered, started.elapsed());
+            result
+        } else {
+            self.execute_stages(capture_output)
+        };
+        if let Ok((output, _)) = &result {
+            if !original_suppress {
+                echo_output(output);
+            }
+        }
+        result
+    }
 
-        // Call spawn_with_io with echo suppressed to avoid double echo
+    /// Spawn the stages of an already echoed pipeline and wait for them.
+    fn execute_stages(mut self, capture_output: bool) -> Result<(Vec<u8>, Vec<ExitStatus>), Error> {
+        // Extract input and output label before moving self
+        let input = self.take_fed_input();
+        let label = self
+            .connections
+            .iter()
+            .rev()
+            .find_map(|(cmd, _)| cmd.label.clone());
+
+        // Kept to name the command if it fails
+        let rendered = self.render(false);
+        let allow_failure = self.allow_failure;
+        let pipefail = self.pipefail;
+        let buffer_size = self.buffer_size;
+        let tail_lines = self.tail_lines;
+
+        // Output that is neither captured nor labeled goes straight to the terminal
+        if !capture_output && label.is_none() {
+            if let Some((last, _)) = self.connections.last_mut() {
+                last.inherit = true;
+            }
+        }
+
+        // Spawn with echo suppressed to avoid double echo
         self.suppress_echo = true;
-        let spawn = self.spawn_with_io()?;
+        let spawn = self.spawn_stages(input.is_some())?;
 
         // Handle input if provided (for backward compatibility)
-        let input_handle = match input {
-            Some(CmdInput::Bytes(bytes)) => {
-                spawn.stdin.map(|mut stdin| {
-                    thread::spawn(move || {
-                        use std::io::Write;
-                        let _ = stdin.write_all(&bytes);
-                        drop(stdin); // Close stdin to signal EOF
-                    })
-                })
-            }
-            Some(CmdInput::Reader(mut reader)) => {
-                spawn.stdin.map(|stdin| {
-                    thread::spawn(move || {
-                        use std::io::copy;
-                        let mut stdin = stdin;
-                        let _ = copy(&mut reader, &mut stdin);
-                        drop(stdin); // Close stdin to signal EOF
-                    })
-                })
-            }
-            None => None,
-        };
+        let input_handle = feed_input(input, spawn.stdin, buffer_size);
 
-        if capture_output {
+        if let (false, Some(label)) = (capture_output, label) {
+            // Stream both output streams with each line tagged by the label
+            let stderr_handle = spawn.stderr.map(|stderr| {
+                let label = label.clone();
+                thread::spawn(move || copy_labeled_lines(stderr, std::io::stderr(), &label))
+            });
             if let Some(stdout) = spawn.stdout {
-                let mut output = Vec::new();
-                let mut reader = BufReader::new(stdout);
-                reader.read_to_end(&mut output).map_err(|e| Error {
-                    message: "Failed to read stdout".to_string(),
-                    source: Some(e),
-                })?;
-
-                // Wait for input thread to complete if exists
-                if let Some(handle) = input_handle {
-                    let _ = handle.join();
-                }
-
-                spawn.handle.wait()?;
-                Ok(output)
-            } else {
-                // Wait for input thread to complete if exists
-                if let Some(handle) = input_handle {
-                    let _ = handle.join();
-                }
-
-                spawn.handle.wait()?;
-                Ok(Vec::new())
+                let _ = copy_labeled_lines(stdout, std::io::stdout(), &label);
             }
-        } else {
-            // Wait for input thread to complete if exists
+            if let Some(handle) = stderr_handle {
+                let _ = handle.join();
+            }
+
             if let Some(handle) = input_handle {
                 let _ = handle.join();
             }
 
-            spawn.handle.wait()?;
-            Ok(Vec::new())
+            let statuses = spawn.handle.wait_statuses()?;
+            let status = overall_status(&statuses, pipefail);
+            if !allow_failure && !status.success() {
+                return Err(Error::exit(&status, Vec::new(), String::new()).with_command(&rendered));
+            }
+            return Ok((Vec::new(), statuses));
+        }
+
+        // Collect stderr concurrently so that no stage blocks on a full pipe while
+        // stdout is being read; it is reported if the command fails
+        let stderr = Arc::new(Mutex::new(Vec::new()));
+        let stderr_handle = spawn
+            .stderr
+            .map(|pipe| collect_stream(pipe, Some(Arc::clone(&stderr)), buffer_size));
+
+        let mut output = Vec::new();
+        if let Some(stdout) = spawn.stdout {
+            let mut reader = BufReader::with_capacity(buffer_size, stdout);
+            let result = if capture_output {
+                match tail_lines {
+                    Some(n) => read_tail_lines(&mut reader, n, &mut output),
+                    None => reader.read_to_end(&mut output).map(|_| ()),
+                }
+            } else {
+                // Not captured, but still drained
+                std::io::copy(&mut reader, &mut std::io::sink()).map(|_| ())
+            };
+            result.map_err(|e| Error::io("Failed to read stdout", e))?;
+        }
+
+        if let Some(handle) = stderr_handle {
+            let _ = handle.join();
+        }
+
+        // Wait for input thread to complete if exists
+        if let Some(handle) = input_handle {
+            let _ = handle.join();
+        }
+
+        let statuses = spawn.handle.wait_statuses()?;
+        let status = overall_status(&statuses, pipefail);
+        if !allow_failure && !status.success() {
+            let stderr = stderr.lock().unwrap_or_else(|e| e.into_inner());
+            return Err(Error::exit(
+                &status,
+                output,
+                String::from_utf8_lossy(&stderr).into_owned(),
+            )
+            .with_command(&rendered));
         }
+        Ok((output, statuses))
     }
 
-    fn build_std_command_static(cmd_def: &Cmd) -> StdCommand {
+    pub(crate) fn build_std_command_static(cmd_def: &Cmd) -> StdCommand {
         let mut cmd = StdCommand::new(&cmd_def.program);
         cmd.args(&cmd_def.args);
 
+        if cmd_def.env_clear {
+            cmd.env_clear();
+        }
+        for key in &cmd_def.env_keeps {
+            if let Some(val) = std::env::var_os(key) {
+                cmd.env(key, val);
+            }
+        }
+        for key in &cmd_def.env_removes {
+            cmd.env_remove(key);
+        }
         for (key, val) in &cmd_def.envs {
             cmd.env(key, val);
         }
@@ -481,18 +1336,98 @@ impl Pipeline {
             cmd.current_dir(current_dir);
         }
 
+        #[cfg(unix)]
+        if let Some(arg0) = &cmd_def.arg0 {
+            use std::os::unix::process::CommandExt;
+            cmd.arg0(arg0);
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::CommandExt;
+            if cmd_def.new_process_group {
+                cmd.process_group(0);
+            }
+            if let Some(gid) = cmd_def.gid {
+                cmd.gid(gid);
+            }
+            if let Some(uid) = cmd_def.uid {
+                cmd.uid(uid);
+            }
+        }
+
         cmd
     }
 
-    fn echo_pipeline(&self) {
-        if !crate::output::should_echo() {
+    pub(crate) fn echo_pipeline(&self) {
+        if !crate::output::should_echo() || record::is_recording() {
             return;
         }
 
+        // With the `tracing` feature the echo becomes an event for the
+        // application's subscriber
+        #[cfg(feature = "tracing")]
+        self.trace_pipeline();
+
+        #[cfg(not(feature = "tracing"))]
+        {
+            let prefix = crate::color::echo_style(crate::color::theme().prefix);
+            crate::output::conditional_eprintln(format_args!(
+                "{prefix}cmd{prefix:#} {}",
+                self.render(true)
+            ));
+        }
+    }
+
+    /// Emit the echo as a `tracing` event instead of printing it.
+    ///
+    /// `program`, `args` and `cwd` describe the first command; `command` is the
+    /// whole pipeline as it would have been echoed.
+    #[cfg(feature = "tracing")]
+    fn trace_pipeline(&self) {
+        let Some((first, _)) = self.connections.first() else {
+            return;
+        };
+        let args: Vec<String> = first.args.iter().map(|a| Cmd::quote_argument(a)).collect();
+        let cwd = first
+            .current_dir
+            .as_ref()
+            .or(self.current_dir.as_ref())
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_default();
+        let command = self.render(false);
+        tracing::info!(
+            target: "scriptify",
+            program = %first.program.to_string_lossy(),
+            args = %args.join(" "),
+            cwd = %cwd,
+            command = %command,
+            "cmd {command}"
+        );
+    }
+
+    /// Render the pipeline as a command line, optionally with ANSI styles.
+    ///
+    /// This is the representation used for echoing, without the `cmd` prefix.
+    pub(crate) fn render(&self, styled: bool) -> String {
+        let theme = crate::color::theme();
+        let paint = |style: anstyle::Style, text: &str| {
+            if styled {
+                let style = crate::color::echo_style(style);
+                format!("{style}{text}{style:#}")
+            } else {
+                text.to_string()
+            }
+        };
+
         let mut parts = Vec::new();
 
-        // Add cmd prefix
-        parts.push(format!("{BRIGHT_BLACK}cmd{BRIGHT_BLACK:#}"));
+        // A pipeline-level directory is shown once instead of for each stage
+        if let Some(current_dir) = &self.current_dir {
+            let quoted_dir = Cmd::quote_argument(current_dir.as_os_str());
+            parts.push(paint(theme.keyword, "cd:"));
+            parts.push(paint(theme.value, &quoted_dir));
+        }
 
         for (i, (cmd, pipe_mode)) in self.connections.iter().enumerate() {
             if i > 0 {
@@ -501,39 +1436,382 @@ impl Pipeline {
                     PipeMode::Stderr => "|&",
                     PipeMode::Both => "|&&",
                 };
-                parts.push(format!("{MAGENTA}{pipe_symbol}{MAGENTA:#}"));
+                parts.push(paint(theme.operator, pipe_symbol));
             }
 
             // Add current directory if set
-            if let Some(current_dir) = &cmd.current_dir {
+            if let Some(current_dir) = cmd
+                .current_dir
+                .as_ref()
+                .filter(|dir| self.current_dir.as_ref() != Some(*dir))
+            {
                 let quoted_dir = Cmd::quote_argument(current_dir.as_os_str());
-                parts.push(format!("{BRIGHT_BLUE}cd:{BRIGHT_BLUE:#}"));
-                parts.push(format!(
-                    "{UNDERLINE_BRIGHT_BLUE}{quoted_dir}{UNDERLINE_BRIGHT_BLUE:#}"
-                ));
+                parts.push(paint(theme.keyword, "cd:"));
+                parts.push(paint(theme.value, &quoted_dir));
             }
 
             // Add environment variables
+            if cmd.env_clear {
+                parts.push(paint(theme.keyword, "env-clear"));
+            }
+            for key in &cmd.env_keeps {
+                let quoted_key = Cmd::quote_argument(key);
+                parts.push(paint(theme.keyword, &format!("env-keep:{quoted_key}")));
+            }
+            for key in &cmd.env_removes {
+                let quoted_key = Cmd::quote_argument(key);
+                parts.push(paint(theme.keyword, &format!("env-rm:{quoted_key}")));
+            }
             for (key, val) in &cmd.envs {
                 let quoted_key = Cmd::quote_argument(key);
                 let quoted_val = Cmd::quote_argument(val);
-                parts.push(format!("{BRIGHT_BLUE}env:{BRIGHT_BLUE:#}"));
-                parts.push(format!(
-                    "{UNDERLINE_BRIGHT_BLUE}{quoted_key}={quoted_val}{UNDERLINE_BRIGHT_BLUE:#}"
-                ));
+                parts.push(paint(theme.keyword, "env:"));
+                parts.push(paint(theme.value, &format!("{quoted_key}={quoted_val}")));
             }
 
             // Add program
             let quoted_program = Cmd::quote_argument(&cmd.program);
-            parts.push(format!("{BOLD_CYAN}{quoted_program}{BOLD_CYAN:#}"));
+            parts.push(paint(theme.program, &quoted_program));
+            if let Some(arg0) = &cmd.arg0 {
+                let quoted_arg0 = Cmd::quote_argument(arg0);
+                parts.push(paint(theme.keyword, &format!("(argv0={quoted_arg0})")));
+            }
+            if let Some(uid) = cmd.uid {
+                parts.push(paint(theme.keyword, &format!("(uid={uid})")));
+            }
+            if let Some(gid) = cmd.gid {
+                parts.push(paint(theme.keyword, &format!("(gid={gid})")));
+            }
 
             // Add arguments
             for arg in &cmd.args {
                 let quoted_arg = Cmd::quote_argument(arg);
-                parts.push(format!("{BOLD_UNDERLINE}{quoted_arg}{BOLD_UNDERLINE:#}"));
+                parts.push(paint(theme.arg, &quoted_arg));
+            }
+
+            // Add redirections
+            for (symbol, redirect) in [(">", &cmd.stdout_redirect), ("2>", &cmd.stderr_redirect)] {
+                match redirect {
+                    Some(Redirect::File { path, append }) => {
+                        let symbol = if *append {
+                            format!("{symbol}>")
+                        } else {
+                            symbol.to_string()
+                        };
+                        let quoted_path = Cmd::quote_argument(path.as_os_str());
+                        parts.push(paint(theme.operator, &symbol));
+                        parts.push(paint(theme.value, &quoted_path));
+                    }
+                    Some(Redirect::Null) => {
+                        parts.push(paint(theme.operator, symbol));
+                        parts.push(paint(theme.value, "/dev/null"));
+                    }
+                    None => {}
+                }
+            }
+            match (i, &self.input) {
+                (0, Some(CmdInput::File(path))) => {
+                    let quoted_path = Cmd::quote_argument(path.as_os_str());
+                    parts.push(paint(theme.operator, "<"));
+                    parts.push(paint(theme.value, &quoted_path));
+                }
+                (0, Some(CmdInput::Null)) => {
+                    parts.push(paint(theme.operator, "<"));
+                    parts.push(paint(theme.value, "/dev/null"));
+                }
+                _ => {}
             }
         }
 
-        eprintln!("{}", parts.join(" "));
+        parts.join(" ")
+    }
+}
+
+/// Formats the pipeline as the plain command line it is echoed as, without
+/// running it.
+///
+/// ```
+/// use scriptify::cmd;
+///
+/// let pipeline = cmd!("ls", "My Documents").pipe(cmd!("wc", "-l"));
+/// assert_eq!(pipeline.to_string(), "ls 'My Documents' | wc -l");
+/// ```
+impl std::fmt::Display for Pipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render(false))
+    }
+}
+
+impl Read for PipelineReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match &mut self.stdout {
+            Some(stdout) => stdout.read(buf),
+            None => Ok(0),
+        }
+    }
+}
+
+impl PipelineReader {
+    /// Wait for the pipeline to finish, as with [`Pipeline::run`].
+    ///
+    /// Output that has not been read yet is discarded, so the commands are never
+    /// blocked writing to it; this waits for a command that never ends, so
+    /// [`kill`](Self::kill) such pipelines first.
+    pub fn wait(mut self) -> Result<(), Error> {
+        if let Some(mut stdout) = self.stdout.take() {
+            std::io::copy(&mut stdout, &mut std::io::sink())
+                .map_err(|e| Error::io("Failed to read stdout", e))?;
+        }
+        if let Some(handle) = self.stderr_reader.take() {
+            let _ = handle.join();
+        }
+        if let Some(handle) = self.input.take() {
+            let _ = handle.join();
+        }
+
+        let Some(handle) = self.handle.take() else {
+            return Ok(());
+        };
+        let rendered = handle.rendered.clone();
+        let status = overall_status(&handle.wait_statuses()?, self.pipefail);
+        if !self.allow_failure && !status.success() {
+            let stderr = self.stderr.lock().unwrap_or_else(|e| e.into_inner());
+            return Err(Error::exit(
+                &status,
+                Vec::new(),
+                String::from_utf8_lossy(&stderr).into_owned(),
+            )
+            .with_command(&rendered));
+        }
+        Ok(())
+    }
+
+    /// Kill all commands of the pipeline, e.g. to stop reading from one that
+    /// never ends. [`wait`](Self::wait) then reports how they were terminated.
+    pub fn kill(&mut self) {
+        for child in self.handle.iter_mut().flat_map(|h| &mut h.children) {
+            let _ = child.kill();
+        }
+    }
+}
+
+impl Drop for PipelineReader {
+    fn drop(&mut self) {
+        if let Some(handle) = &mut self.handle {
+            for child in &mut handle.children {
+                kill_if_running(child);
+            }
+        }
+    }
+}
+
+/// Copy `source` to both `next` and `tee` until `source` is exhausted or the
+/// next command stops reading.
+///
+/// Failing to write to `tee` does not interrupt the pipeline.
+fn spawn_tee(
+    mut source: std::io::PipeReader,
+    mut next: std::io::PipeWriter,
+    TeeWriter(tee): TeeWriter,
+    buffer_size: usize,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut tee = Some(tee);
+        let mut buf = vec![0u8; buffer_size];
+        loop {
+            let n = match source.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            if tee
+                .as_mut()
+                .is_some_and(|w| w.write_all(&buf[..n]).is_err())
+            {
+                tee = None;
+            }
+            if next.write_all(&buf[..n]).is_err() {
+                break;
+            }
+        }
+        if let Some(mut tee) = tee {
+            let _ = tee.flush();
+        }
+    })
+}
+
+/// Joins the tee threads of a pipeline once its commands have been waited for,
+/// including when waiting bails out early.
+struct JoinOnDrop(Vec<thread::JoinHandle<()>>);
+
+impl Drop for JoinOnDrop {
+    fn drop(&mut self) {
+        for handle in self.0.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Write the pipeline input to the first command's stdin from a separate thread.
+///
+/// Stdin is closed once the input is exhausted so the command sees EOF.
+fn feed_input(
+    input: Option<CmdInput>,
+    stdin: Option<std::process::ChildStdin>,
+    buffer_size: usize,
+) -> Option<thread::JoinHandle<()>> {
+    let input = input?;
+    let mut stdin = stdin?;
+    Some(thread::spawn(move || {
+        match input {
+            CmdInput::Bytes(bytes) => {
+                let _ = stdin.write_all(&bytes);
+            }
+            CmdInput::Reader(reader) => {
+                let mut reader = BufReader::with_capacity(buffer_size, reader);
+                let _ = std::io::copy(&mut reader, &mut stdin);
+            }
+            CmdInput::File(path) => {
+                if let Ok(file) = std::fs::File::open(path) {
+                    let mut reader = BufReader::with_capacity(buffer_size, file);
+                    let _ = std::io::copy(&mut reader, &mut stdin);
+                }
+            }
+            CmdInput::Null | CmdInput::Inherit => {}
+        }
+        drop(stdin); // Close stdin to signal EOF
+    }))
+}
+
+/// Echo how long the pipeline rendered as `rendered` took, e.g. `cmd sleep 1 (1.00s)`.
+fn echo_elapsed(rendered: &str, elapsed: Duration) {
+    if !crate::output::should_echo() {
+        return;
+    }
+
+    let prefix = crate::color::echo_style(crate::color::theme().prefix);
+    crate::output::conditional_eprintln(format_args!(
+        "{prefix}cmd{prefix:#} {rendered} {prefix}({:.2}s){prefix:#}",
+        elapsed.as_secs_f64()
+    ));
+}
+
+/// Echo output captured from a pipeline, at verbosity `CommandsAndOutput` and above.
+fn echo_output(output: &[u8]) {
+    if output.is_empty() || crate::verbosity() < crate::Verbosity::CommandsAndOutput {
+        return;
+    }
+
+    let text = String::from_utf8_lossy(output);
+    crate::output::conditional_eprintln(format_args!(
+        "{}",
+        text.strip_suffix('\n').unwrap_or(&text)
+    ));
+}
+
+/// Read `reader` to EOF, appending only its last `n` lines to `output`.
+///
+/// At most `n` lines are held in memory at a time, and their buffers are reused.
+fn read_tail_lines(
+    reader: &mut impl BufRead,
+    n: usize,
+    output: &mut Vec<u8>,
+) -> std::io::Result<()> {
+    if n == 0 {
+        return std::io::copy(reader, &mut std::io::sink()).map(|_| ());
+    }
+
+    let mut lines: VecDeque<Vec<u8>> = VecDeque::new();
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        let next = if lines.len() == n {
+            lines.pop_front().unwrap_or_default()
+        } else {
+            Vec::new()
+        };
+        lines.push_back(std::mem::replace(&mut line, next));
+        line.clear();
+    }
+    for line in lines {
+        output.extend_from_slice(&line);
+    }
+    Ok(())
+}
+
+/// Writer that forwards everything to `writer` and keeps a copy.
+struct Tee<W> {
+    writer: W,
+    captured: Vec<u8>,
+}
+
+impl<W: Write> Write for Tee<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.writer.write(buf)?;
+        self.captured.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Open a file to be used as the stdin of a command.
+fn open_input_file(path: &Path) -> Result<Stdio, Error> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| Error::io(format!("Failed to open input file: {}", path.display()), e))?;
+    Ok(Stdio::from(file))
+}
+
+/// Read a stream to EOF on a separate thread, appending it to `sink` if given.
+///
+/// The stream is always drained so that the writing process never blocks on a full pipe.
+pub(crate) fn collect_stream<R: Read + Send + 'static>(
+    mut stream: R,
+    sink: Option<Arc<Mutex<Vec<u8>>>>,
+    buffer_size: usize,
+) -> thread::JoinHandle<std::io::Result<()>> {
+    thread::spawn(move || {
+        let mut chunk = vec![0u8; buffer_size];
+        loop {
+            let n = stream.read(&mut chunk)?;
+            if n == 0 {
+                return Ok(());
+            }
+            if let Some(sink) = &sink {
+                sink.lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .extend_from_slice(&chunk[..n]);
+            }
+        }
+    })
+}
+
+/// Copy lines from `reader` to `writer`, prefixing each with `[label] `.
+///
+/// Each line is written with a single call on the locked writer so that lines
+/// from concurrently running commands don't interleave.
+pub(crate) fn copy_labeled_lines<R: Read, W: Write>(
+    reader: R,
+    mut writer: W,
+    label: &str,
+) -> std::io::Result<()> {
+    use std::io::BufRead;
+
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        if !line.ends_with(b"\n") {
+            line.push(b'\n');
+        }
+        let mut tagged = format!("[{label}] ").into_bytes();
+        tagged.extend_from_slice(&line);
+        writer.write_all(&tagged)?;
+        writer.flush()?;
     }
 }
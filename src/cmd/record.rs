@@ -0,0 +1,76 @@
+//! Recording commands instead of executing them.
+
+use crate::cmd::types::{Pipeline, RecordedCommand};
+use std::cell::RefCell;
+
+thread_local! {
+    static RECORDING: RefCell<Option<Vec<RecordedCommand>>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, recording the commands it would run instead of executing them.
+///
+/// While `f` runs, every command or pipeline executed on the current thread is
+/// captured rather than spawned: `run()` succeeds, `output()` returns empty
+/// output, and nothing is echoed. The recorded commands are returned in order,
+/// which enables "plan, preview, then apply" workflows. Commands executed on
+/// other threads are not affected.
+///
+/// # Examples
+///
+/// ```
+/// use scriptify::*;
+///
+/// fn deploy() -> Result<()> {
+///     cmd!("cargo", "build", "--release").run()?;
+///     cmd!("rsync", "-a", "target/release/app", "server:/opt/app").run()?;
+///     Ok(())
+/// }
+///
+/// let plan = record(|| {
+///     deploy().unwrap();
+/// });
+/// assert_eq!(plan.len(), 2);
+/// assert_eq!(plan[0].rendered, "cargo build --release");
+/// ```
+pub fn record(f: impl FnOnce()) -> Vec<RecordedCommand> {
+    // Restores the previous state even if `f` panics
+    struct Restore(Option<Vec<RecordedCommand>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            RECORDING.with(|recording| *recording.borrow_mut() = self.0.take());
+        }
+    }
+
+    let _restore = Restore(RECORDING.with(|recording| recording.replace(Some(Vec::new()))));
+    f();
+    RECORDING.with(|recording| recording.borrow_mut().take().unwrap_or_default())
+}
+
+/// Returns `true` if commands on this thread are currently being recorded.
+pub(crate) fn is_recording() -> bool {
+    RECORDING.with(|recording| recording.borrow().is_some())
+}
+
+/// Returns `true` if the pipeline must not be spawned, either because it was
+/// recorded or because dry-run mode is on.
+pub(crate) fn skip_execution(pipeline: &Pipeline) -> bool {
+    try_record(pipeline) || crate::dry_run::is_dry_run()
+}
+
+/// Record the pipeline if recording is active, returning whether it was recorded.
+fn try_record(pipeline: &Pipeline) -> bool {
+    RECORDING.with(|recording| match recording.borrow_mut().as_mut() {
+        Some(recorded) => {
+            recorded.push(RecordedCommand {
+                rendered: pipeline.render(false),
+                commands: pipeline
+                    .connections
+                    .iter()
+                    .map(|(cmd, _)| cmd.clone())
+                    .collect(),
+            });
+            true
+        }
+        None => false,
+    })
+}
@@ -0,0 +1,64 @@
+//! Exit status of finished commands.
+
+use std::process::ExitStatus;
+
+/// Exit status of a finished command or pipeline.
+///
+/// For a pipeline this is the status of the first stage that failed, or of the
+/// last stage if all of them succeeded. With
+/// [`pipefail(false)`](crate::Pipeline::pipefail) it is always the last stage's.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    pub(crate) inner: ExitStatus,
+}
+
+impl Status {
+    /// Whether the command exited successfully.
+    pub fn success(&self) -> bool {
+        self.inner.success()
+    }
+
+    /// The exit code, or `None` if the command was terminated by a signal.
+    pub fn code(&self) -> Option<i32> {
+        self.inner.code()
+    }
+
+    /// The number of the signal that terminated the command, if any (Unix only).
+    ///
+    /// Distinguishes a command killed by a signal (e.g. `SIGKILL` from the OOM
+    /// killer) from one that exited with a non-zero code.
+    #[cfg(unix)]
+    pub fn signal(&self) -> Option<i32> {
+        use std::os::unix::process::ExitStatusExt;
+        self.inner.signal()
+    }
+
+    /// The underlying [`ExitStatus`].
+    pub fn exit_status(&self) -> ExitStatus {
+        self.inner
+    }
+}
+
+impl From<ExitStatus> for Status {
+    fn from(inner: ExitStatus) -> Self {
+        Status { inner }
+    }
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+/// The status representing a whole pipeline: with `pipefail`, the first
+/// failure, or the last stage's status if every stage succeeded; without it,
+/// always the last stage's status.
+pub(crate) fn overall_status(statuses: &[ExitStatus], pipefail: bool) -> ExitStatus {
+    statuses
+        .iter()
+        .find(|status| pipefail && !status.success())
+        .or(statuses.last())
+        .copied()
+        .unwrap_or_default()
+}
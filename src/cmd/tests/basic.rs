@@ -5,7 +5,8 @@
 
 use super::*;
 use crate::cmd;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
+use std::path::Path;
 
 /// Tests basic command creation with `Cmd::new()`
 #[test]
@@ -27,6 +28,22 @@ fn test_cmd_with_args() {
     );
 }
 
+/// Tests splatting collections into the arguments with `..`
+#[test]
+fn test_cmd_splat_args() {
+    let files: Vec<String> = vec!["a.txt".into(), "b c.txt".into()];
+    let cmd = cmd!("tar", "-czf", "out.tgz", ..&files, "last",);
+    assert_eq!(cmd.program, OsString::from("tar"));
+    assert_eq!(cmd.args, ["-czf", "out.tgz", "a.txt", "b c.txt", "last"]);
+
+    // Owned collections, iterators and a splat in the first position
+    let cmd = cmd!("echo", ..files, ..["x", "y"].iter().rev());
+    assert_eq!(cmd.args, ["a.txt", "b c.txt", "y", "x"]);
+
+    let empty: Vec<String> = Vec::new();
+    assert!(cmd!("true", ..empty).args.is_empty());
+}
+
 /// Tests the builder pattern for command configuration
 #[test]
 fn test_cmd_builder() {
@@ -95,6 +112,41 @@ fn test_args_method() {
     );
 }
 
+/// Tests the conditional `arg_if()`, `args_if()` and `arg_opt()` methods
+#[test]
+fn test_conditional_args() {
+    let cmd = Cmd::new("ls").arg_if(true, "-l").arg_if(false, "-a");
+    assert_eq!(cmd.args, vec![OsString::from("-l")]);
+
+    let cmd = Cmd::new("ls")
+        .args_if(false, ["-a", "-h"])
+        .args_if(true, ["-r", "-t"]);
+    assert_eq!(cmd.args, vec![OsString::from("-r"), OsString::from("-t")]);
+
+    let cmd = Cmd::new("ls").arg_opt(Some("dir")).arg_opt(None::<&str>);
+    assert_eq!(cmd.args, vec![OsString::from("dir")]);
+}
+
+/// Tests the `arg_display()` method for `Display` arguments
+#[test]
+fn test_arg_display() {
+    let cmd = Cmd::new("head")
+        .arg("-n")
+        .arg_display(10u32)
+        .arg_display('x');
+    assert_eq!(
+        cmd.args,
+        vec![
+            OsString::from("-n"),
+            OsString::from("10"),
+            OsString::from("x")
+        ]
+    );
+
+    let output = cmd!("echo").arg_display(42).no_echo().output().unwrap();
+    assert_eq!(output.trim(), "42");
+}
+
 /// Tests that all builder methods work correctly in combination
 #[test]
 fn test_builder_pattern_completeness() {
@@ -130,3 +182,180 @@ fn test_builder_pattern_completeness() {
     assert_eq!(cmd.current_dir, Some(temp_dir));
     assert!(cmd.suppress_echo);
 }
+
+/// Tests opt-in expansion of `@file` arguments
+#[test]
+fn test_expand_arg_files() {
+    let dir = std::env::temp_dir().join(format!("scriptify_argfile_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("args.txt"),
+        "one two\n'three four' \"five \\\"six\\\"\"\n  seven\\ eight\n",
+    )
+    .unwrap();
+
+    let output = cmd!("printf", "[%s]", "@args.txt", "last")
+        .current_dir(&dir)
+        .expand_arg_files()
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(
+        output,
+        "[one][two][three four][five \"six\"][seven eight][last]"
+    );
+
+    // Without opting in, the argument is passed through unchanged
+    let output = cmd!("printf", "[%s]", "@args.txt")
+        .current_dir(&dir)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "[@args.txt]");
+
+    // Missing files and unterminated quotes are reported
+    let error = cmd!("echo", "@missing.txt")
+        .current_dir(&dir)
+        .expand_arg_files()
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert!(error.to_string().contains("missing.txt"));
+
+    std::fs::write(dir.join("bad.txt"), "'unterminated").unwrap();
+    let error = cmd!("echo", "@bad.txt")
+        .current_dir(&dir)
+        .expand_arg_files()
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert!(error.to_string().contains("unterminated quote"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Tests starting a command in its own process group
+#[cfg(unix)]
+#[test]
+fn test_new_process_group() {
+    let mut handle = cmd!("sleep", "0.1")
+        .new_process_group()
+        .no_echo()
+        .spawn()
+        .unwrap();
+    assert!(handle.wait().unwrap().success());
+
+    // The shell is the leader of its group: its pgid equals its pid
+    #[cfg(target_os = "linux")]
+    {
+        let output = cmd!("sh", "-c", "echo $$; cut -d' ' -f5 /proc/$$/stat")
+            .new_process_group()
+            .no_echo()
+            .output()
+            .unwrap();
+        let ids: Vec<&str> = output.lines().collect();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[0], ids[1]);
+    }
+}
+
+/// Tests running a command as another user
+#[cfg(unix)]
+#[test]
+fn test_uid_gid() {
+    let cmd = cmd!("id", "-u").uid(65534).gid(65534);
+    assert_eq!(cmd.uid, Some(65534));
+    assert_eq!(cmd.gid, Some(65534));
+    assert_eq!(
+        cmd.clone().into_pipeline().render(false),
+        "id (uid=65534) (gid=65534) -u"
+    );
+
+    // Switching users is only permitted for root
+    let is_root = cmd!("id", "-u").no_echo().output().unwrap().trim() == "0";
+    if is_root {
+        let output = cmd.no_echo().output().unwrap();
+        assert_eq!(output.trim(), "65534");
+    }
+}
+
+/// Tests overriding `argv[0]`
+#[cfg(unix)]
+#[test]
+fn test_arg0() {
+    let output = cmd!("sh", "-c", "echo $0")
+        .arg0("-custom-shell")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "-custom-shell");
+
+    let rendered = cmd!("busybox", "ls")
+        .arg0("ls")
+        .into_pipeline()
+        .render(false);
+    assert_eq!(rendered, "busybox (argv0=ls) ls");
+}
+
+/// Tests converting into a `std::process::Command`
+#[test]
+fn test_into_std() {
+    let command = cmd!("ls", "-l", "/tmp")
+        .env("LANG", "C")
+        .current_dir("/")
+        .into_std();
+    assert_eq!(command.get_program(), "ls");
+    assert_eq!(command.get_args().collect::<Vec<_>>(), ["-l", "/tmp"]);
+    assert_eq!(
+        command.get_envs().collect::<Vec<_>>(),
+        [(OsStr::new("LANG"), Some(OsStr::new("C")))]
+    );
+    assert_eq!(command.get_current_dir(), Some(Path::new("/")));
+
+    let output = cmd!("echo", "converted").into_std().output().unwrap();
+    assert_eq!(output.stdout, b"converted\n");
+}
+
+/// Tests the callbacks observing a command's spawn and exit
+#[test]
+fn test_on_spawn_on_exit() {
+    use std::sync::{Arc, Mutex};
+
+    let pids = Arc::new(Mutex::new(Vec::new()));
+    let exits = Arc::new(Mutex::new(Vec::new()));
+    let observed = |cmd: Cmd| {
+        let pids = Arc::clone(&pids);
+        let exits = Arc::clone(&exits);
+        cmd.no_echo()
+            .on_spawn(move |pid| pids.lock().unwrap().push(pid))
+            .on_exit(move |status, elapsed| exits.lock().unwrap().push((status.code(), elapsed)))
+    };
+
+    observed(cmd!("sleep", "0.1")).run().unwrap();
+    assert_eq!(pids.lock().unwrap().len(), 1);
+    let (code, elapsed) = exits.lock().unwrap()[0];
+    assert_eq!(code, Some(0));
+    assert!(elapsed >= std::time::Duration::from_millis(100));
+
+    // Called for a failing command before the error is returned
+    exits.lock().unwrap().clear();
+    assert!(observed(cmd!("sh", "-c", "exit 3")).run().is_err());
+    assert_eq!(exits.lock().unwrap()[0].0, Some(3));
+
+    // Each observed stage of a pipeline reports itself
+    exits.lock().unwrap().clear();
+    let output = observed(cmd!("echo", "hi"))
+        .pipe(cmd!("cat"))
+        .pipe(observed(cmd!("tr", "a-z", "A-Z")))
+        .output()
+        .unwrap();
+    assert_eq!(output, "HI\n");
+    assert_eq!(exits.lock().unwrap().len(), 2);
+
+    // A background command reports its exit once
+    exits.lock().unwrap().clear();
+    let mut handle = observed(cmd!("true")).spawn().unwrap();
+    handle.wait().unwrap();
+    handle.wait().unwrap();
+    assert_eq!(exits.lock().unwrap().len(), 1);
+}
@@ -324,3 +324,239 @@ fn test_deadlock_prevention() {
         handle.join().unwrap();
     }
 }
+
+/// Tests streaming a command's stdout into a buffer shared with another thread
+#[test]
+fn test_capture_into_shared_buffer() {
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    let handle = cmd!("sh", "-c", "echo first; sleep 0.3; echo second")
+        .no_echo()
+        .capture_into(Arc::clone(&buf))
+        .unwrap();
+
+    // The first line becomes visible while the command is still running
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !String::from_utf8_lossy(&buf.lock().unwrap()).contains("first") {
+        assert!(Instant::now() < deadline, "first line never arrived");
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    handle.wait().unwrap();
+    assert_eq!(
+        String::from_utf8_lossy(&buf.lock().unwrap()),
+        "first\nsecond\n"
+    );
+
+    // Failures are still reported when waiting
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    let handle = cmd!("sh", "-c", "echo partial; exit 1")
+        .no_echo()
+        .capture_into(Arc::clone(&buf))
+        .unwrap();
+    assert!(handle.wait().is_err());
+    assert_eq!(*buf.lock().unwrap(), b"partial\n");
+
+    // Redirected stdout goes to its target instead of the buffer
+    let path = std::env::temp_dir().join(format!("scriptify_capture_into_{}", std::process::id()));
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    cmd!("echo", "to file")
+        .stdout_to(&path)
+        .no_echo()
+        .capture_into(Arc::clone(&buf))
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert!(buf.lock().unwrap().is_empty());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "to file\n");
+    std::fs::remove_file(&path).unwrap();
+}
+
+/// Tests that labelled output lines are prefixed with the command label
+#[test]
+fn test_label_prefixes_output_lines() {
+    use crate::cmd::pipeline::copy_labeled_lines;
+    use std::io::Cursor;
+
+    let mut out = Vec::new();
+    copy_labeled_lines(Cursor::new("one\ntwo\nthree"), &mut out, "web").unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "[web] one\n[web] two\n[web] three\n"
+    );
+
+    // Labelled commands still run and report failures
+    let cmd = cmd!("echo", "hello").label("api");
+    assert_eq!(cmd.label.as_deref(), Some("api"));
+    assert!(cmd.no_echo().run().is_ok());
+    assert!(cmd!("false").label("api").no_echo().run().is_err());
+
+    // Labelled runs can execute concurrently
+    let handles: Vec<_> = (0..4)
+        .map(|i| {
+            std::thread::spawn(move || {
+                cmd!("sh", "-c", "echo a; echo b >&2")
+                    .label(&format!("job{i}"))
+                    .no_echo()
+                    .run()
+            })
+        })
+        .collect();
+    for handle in handles {
+        assert!(handle.join().unwrap().is_ok());
+    }
+}
+
+/// Tests that echoed lines from concurrent commands are never interleaved
+#[cfg(not(feature = "tracing"))]
+#[test]
+#[serial_test::serial]
+fn test_concurrent_echo_lines_are_atomic() {
+    use crate::test_util::SharedBuf;
+    use std::thread;
+
+    let buffer = SharedBuf::default();
+    crate::set_echo_writer(buffer.clone());
+
+    let handles: Vec<_> = (0..8)
+        .map(|t| {
+            thread::spawn(move || {
+                for i in 0..10 {
+                    cmd!("echo", "echo-marker", format!("t{t}-i{i}"), "x".repeat(200))
+                        .pipe(cmd!("cat"))
+                        .output()
+                        .unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    crate::reset_echo_writer();
+
+    let trace = buffer.contents();
+    let lines: Vec<&str> = trace
+        .lines()
+        .filter(|line| line.contains("echo-marker"))
+        .collect();
+    assert_eq!(lines.len(), 80);
+    for line in lines {
+        // Exactly one command per line, rendered in full
+        assert_eq!(line.matches("echo-marker").count(), 1, "{line:?}");
+        assert!(line.contains(&"x".repeat(200)), "{line:?}");
+        assert!(line.contains("cat"), "{line:?}");
+    }
+}
+
+/// Tests large intermediate and final output in pipelines does not deadlock
+#[test]
+fn test_pipeline_large_output_no_deadlock() {
+    // An early stage produces far more than a pipe buffer holds
+    let expected = (1..=1_000_000)
+        .filter(|n: &u32| n.to_string().contains('5'))
+        .count();
+    let output = cmd!("seq", "1000000")
+        .pipe(cmd!("grep", "5"))
+        .pipe(cmd!("wc", "-l"))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim().parse::<usize>().unwrap(), expected);
+
+    // Large stderr from the last stage while stdout is captured
+    let output = cmd!("sh", "-c", "seq 200000 >&2; echo done")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "done\n");
+
+    // Large output that is not captured
+    cmd!("seq", "200000").no_echo().run().unwrap();
+    cmd!("sh", "-c", "seq 200000 >&2")
+        .pipe(cmd!("seq", "200000"))
+        .no_echo()
+        .run()
+        .unwrap();
+}
+
+/// Tests streaming to a writer while the last stage floods stderr
+#[test]
+fn test_stream_to_with_large_stderr() {
+    let mut out = Vec::new();
+    cmd!("sh", "-c", "seq 200000 >&2; echo streamed")
+        .no_echo()
+        .stream_to(&mut out)
+        .unwrap();
+    assert_eq!(out, b"streamed\n");
+
+    let mut out = Vec::new();
+    cmd!("sh", "-c", "seq 200000 >&2; cat")
+        .no_echo()
+        .run_with_io(std::io::Cursor::new(b"piped".to_vec()), &mut out)
+        .unwrap();
+    assert_eq!(out, b"piped");
+}
+
+/// Tests spawning a command in the background and killing it
+#[test]
+fn test_spawn_and_kill() {
+    let start = std::time::Instant::now();
+    let mut handle = cmd!("sleep", "2").no_echo().spawn().unwrap();
+    assert!(handle.id().is_some());
+
+    handle.kill().unwrap();
+    let status = handle.wait().unwrap();
+    assert!(!status.success());
+    assert!(start.elapsed() < std::time::Duration::from_secs(2));
+
+    // Killing or waiting again after exit is fine
+    handle.kill().unwrap();
+    assert!(!handle.wait().unwrap().success());
+
+    let mut handle = cmd!("sh", "-c", "exit 3").no_echo().spawn().unwrap();
+    assert_eq!(handle.wait().unwrap().code(), Some(3));
+}
+
+/// Tests that a dropped handle kills its command only if asked to
+#[test]
+fn test_kill_on_drop() {
+    use std::sync::{Arc, Mutex};
+
+    let is_alive = |pid: u32| {
+        cmd!("kill", "-0", pid.to_string())
+            .no_echo()
+            .status()
+            .unwrap()
+            .success()
+    };
+
+    let handle = cmd!("sleep", "10")
+        .kill_on_drop(true)
+        .no_echo()
+        .spawn()
+        .unwrap();
+    let pid = handle.id().unwrap();
+    assert!(is_alive(pid));
+    drop(handle);
+    assert!(!is_alive(pid));
+
+    let buf = Arc::new(Mutex::new(Vec::new()));
+    let handle = cmd!("sleep", "10")
+        .kill_on_drop(true)
+        .no_echo()
+        .capture_into(Arc::clone(&buf))
+        .unwrap();
+    let pid = handle.child.as_ref().unwrap().id();
+    drop(handle);
+    assert!(!is_alive(pid));
+
+    // Without the option the command keeps running
+    let handle = cmd!("sleep", "10").no_echo().spawn().unwrap();
+    let pid = handle.id().unwrap();
+    drop(handle);
+    assert!(is_alive(pid));
+    cmd!("kill", pid.to_string()).no_echo().run().unwrap();
+}
@@ -156,3 +156,211 @@ fn test_empty_environment_variables() {
     // printenv should fail for unset variables
     assert!(result.is_err());
 }
+
+/// Tests that programs are resolved against the command's current `PATH`
+#[test]
+fn test_dynamic_path_lookup() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin_dir = env::temp_dir().join(format!("scriptify_path_{}", std::process::id()));
+    std::fs::create_dir_all(&bin_dir).unwrap();
+    let program = "scriptify-fresh-tool";
+
+    // Not installed yet
+    assert!(cmd!(program).no_echo().run().is_err());
+
+    // "Install" the program and make it visible through PATH
+    let tool = bin_dir.join(program);
+    std::fs::write(&tool, "#!/bin/sh\necho fresh tool\n").unwrap();
+    std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    let path = format!("{}:{}", bin_dir.display(), env::var("PATH").unwrap());
+    let output = cmd!(program).env("PATH", &path).no_echo().output().unwrap();
+    assert_eq!(output.trim(), "fresh tool");
+
+    // refresh_path() copies the current process PATH onto the command
+    let cmd = cmd!("true").refresh_path();
+    assert_eq!(cmd.envs.len(), 1);
+    assert_eq!(cmd.envs[0].0, "PATH");
+    assert_eq!(Some(cmd.envs[0].1.clone()), env::var_os("PATH"));
+    assert!(cmd.no_echo().run().is_ok());
+
+    std::fs::remove_dir_all(&bin_dir).unwrap();
+}
+
+/// Tests the `RUST_BACKTRACE` and `RUST_LOG` helpers
+#[test]
+fn test_rust_env_helpers() {
+    let output = cmd!("sh", "-c", "echo $RUST_BACKTRACE $RUST_LOG")
+        .rust_backtrace(false)
+        .rust_log("my_crate=debug")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "1 my_crate=debug");
+
+    let cmd = cmd!("cargo", "test").rust_backtrace(true);
+    assert_eq!(cmd.envs[0], ("RUST_BACKTRACE".into(), "full".into()));
+}
+
+/// Tests that setting a variable twice keeps only the last value
+#[test]
+fn test_environment_variable_override() {
+    let make = || {
+        cmd!("printenv", "DUP_VAR")
+            .env("DUP_VAR", "first")
+            .env("OTHER_VAR", "kept")
+            .env("DUP_VAR", "second")
+    };
+
+    let output = make().no_echo().output().unwrap();
+    assert_eq!(output.trim(), "second");
+
+    let (_, rendered) = make().no_echo().run_traced().unwrap();
+    assert_eq!(
+        rendered,
+        "env: OTHER_VAR=kept env: DUP_VAR=second printenv DUP_VAR"
+    );
+}
+
+/// Tests starting from an empty environment
+#[test]
+fn test_env_clear() {
+    let result = cmd!("printenv", "PATH").env_clear().no_echo().output();
+    assert!(result.is_err());
+
+    let output = cmd!("env")
+        .env("DROPPED", "1")
+        .env_clear()
+        .env("ONLY_VAR", "kept")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "ONLY_VAR=kept\n");
+
+    let (_, rendered) = cmd!("env")
+        .env_clear()
+        .env("A", "1")
+        .no_echo()
+        .run_traced()
+        .unwrap();
+    assert_eq!(rendered, "env-clear env: A=1 env");
+}
+
+/// Tests passing only some variables of this process's environment
+#[test]
+fn test_env_inherit_only() {
+    assert!(env::var_os("PATH").is_some());
+    assert!(env::var_os("HOME").is_some());
+
+    let output = cmd!("printenv", "PATH")
+        .env_inherit_only(["PATH"])
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim_end(), env::var("PATH").unwrap());
+
+    let result = cmd!("printenv", "HOME")
+        .env_inherit_only(["PATH"])
+        .no_echo()
+        .output();
+    assert!(result.is_err());
+
+    // Unset keys are skipped, later variables are passed
+    let output = cmd!("env")
+        .env_inherit_only(["SCRIPTIFY_SURELY_UNSET", "HOME"])
+        .env("EXTRA", "1")
+        .no_echo()
+        .output()
+        .unwrap();
+    let home = format!("HOME={}", env::var("HOME").unwrap());
+    let mut lines: Vec<_> = output.lines().collect();
+    lines.sort();
+    assert_eq!(lines, ["EXTRA=1", home.as_str()]);
+
+    let rendered = cmd!("make")
+        .env_inherit_only(["PATH", "HOME"])
+        .into_pipeline()
+        .render(false);
+    assert_eq!(rendered, "env-clear env-keep:PATH env-keep:HOME make");
+}
+
+/// Tests removing an inherited environment variable
+#[test]
+fn test_env_remove() {
+    assert!(env::var_os("PATH").is_some());
+    let result = cmd!("printenv", "PATH")
+        .env_remove("PATH")
+        .no_echo()
+        .output();
+    assert!(result.is_err());
+
+    // A variable set earlier is dropped, one set later is passed
+    let output = cmd!("sh", "-c", "echo ${A-unset} ${B-unset}")
+        .env("A", "1")
+        .env_remove("A")
+        .env_remove("B")
+        .env("B", "2")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "unset 2");
+
+    let rendered = cmd!("true")
+        .env_remove("HOME")
+        .into_pipeline()
+        .render(false);
+    assert_eq!(rendered, "env-rm:HOME true");
+}
+
+/// Tests setting several environment variables at once
+#[test]
+fn test_envs() {
+    let vars = vec![("A", "alpha"), ("B", "beta")];
+    let (output, rendered) = cmd!("sh", "-c", "echo $A $B")
+        .envs(vars)
+        .no_echo()
+        .run_traced()
+        .unwrap();
+    assert_eq!(output.stdout, b"alpha beta\n");
+    assert_eq!(rendered, "env: A=alpha env: B=beta sh -c 'echo $A $B'");
+
+    // Later pairs replace earlier ones, as with env()
+    let output = cmd!("printenv", "A")
+        .env("A", "before")
+        .envs([("A", "first"), ("A", "second")])
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "second");
+}
+
+/// Tests that a checked working directory is validated before spawning
+#[test]
+fn test_current_dir_checked() {
+    let missing = env::temp_dir().join("scriptify_missing_dir_12345");
+    let error = cmd!("pwd")
+        .current_dir_checked(&missing)
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!("Working directory does not exist: {}", missing.display())
+    );
+
+    let output = cmd!("pwd")
+        .current_dir_checked(env::temp_dir())
+        .no_echo()
+        .output()
+        .unwrap();
+    let expected = env::temp_dir().canonicalize().unwrap();
+    assert_eq!(output.trim(), expected.to_string_lossy());
+
+    // A file is not a working directory
+    let file = env::temp_dir().join(format!("scriptify_not_a_dir_{}", std::process::id()));
+    std::fs::write(&file, "").unwrap();
+    let result = cmd!("pwd").current_dir_checked(&file).no_echo().run();
+    assert!(result.is_err());
+    std::fs::remove_file(&file).unwrap();
+}
@@ -5,6 +5,7 @@
 
 use super::Cmd;
 use crate::cmd;
+use crate::cmd::ErrorKind;
 use std::ffi::OsString;
 
 /// Tests comprehensive command not found error handling
@@ -17,7 +18,11 @@ fn test_command_not_found_error() {
     // Test that error message is informative
     let error = result.unwrap_err();
     assert!(error.message.contains("Failed to spawn command"));
-    assert!(error.message.contains("nonexistent_command_12345"));
+    assert!(
+        error
+            .message
+            .contains("program 'nonexistent_command_12345' not found on PATH")
+    );
 
     // Test with different non-existent command
     let result = cmd!("this_command_definitely_does_not_exist")
@@ -205,3 +210,399 @@ fn test_error_recovery_workflow() {
     let working_result = cmd!("echo", "still working").no_echo().output().unwrap();
     assert_eq!(working_result.trim(), "still working");
 }
+
+/// Tests that captured stderr is reported when stdout is inherited
+#[test]
+fn test_inherit_stdout_capture_stderr() {
+    let status = cmd!("sh", "-c", "echo progress; echo warning >&2")
+        .no_echo()
+        .inherit_stdout_capture_stderr()
+        .unwrap();
+    assert!(status.success());
+
+    let error = cmd!(
+        "sh",
+        "-c",
+        "echo step 1; echo 'fatal: disk full' >&2; exit 2"
+    )
+    .no_echo()
+    .inherit_stdout_capture_stderr()
+    .unwrap_err();
+    assert!(error.message.contains("exit code 2"));
+    assert_eq!(error.stderr(), "fatal: disk full\n");
+    assert!(error.to_string().contains("fatal: disk full"));
+    assert!(!error.stderr().contains("step 1"));
+}
+
+/// Tests that the install hint is shown only when the program is missing
+#[test]
+fn test_install_hint() {
+    let hint = "install with: brew install ripgrep";
+    let error = cmd!("scriptify-missing-tool-12345")
+        .install_hint(hint)
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert!(error.to_string().contains(hint));
+
+    // Also applies to a stage of a multi-command pipeline
+    let error = cmd!("echo", "x")
+        .pipe(cmd!("scriptify-missing-tool-12345").install_hint(hint))
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert!(error.to_string().contains(hint));
+
+    // Other failures are reported as usual
+    let error = cmd!("sh", "-c", "exit 1")
+        .install_hint(hint)
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert!(!error.to_string().contains(hint));
+}
+
+/// Tests that termination by a signal is reported with the signal name
+#[cfg(unix)]
+#[test]
+fn test_signal_termination_error() {
+    let error = cmd!("sh", "-c", "kill -9 $$").no_echo().run().unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .starts_with("Command terminated by signal 9 (SIGKILL)")
+    );
+
+    let error = cmd!("sh", "-c", "kill -SEGV $$")
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert!(error.to_string().contains("signal 11 (SIGSEGV)"));
+
+    // Regular failures still report the exit code
+    let error = cmd!("sh", "-c", "exit 3").no_echo().run().unwrap_err();
+    assert!(
+        error
+            .to_string()
+            .starts_with("Command failed with exit code 3 (")
+    );
+}
+
+/// Tests that stdout produced before a failure is kept in the error
+#[test]
+fn test_partial_stdout_on_failure() {
+    let error = cmd!("sh", "-c", "echo step 1; echo step 2; exit 1")
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert_eq!(error.partial_stdout(), b"step 1\nstep 2\n");
+
+    let error = cmd!("sh", "-c", "echo built; exit 2")
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .output_bytes()
+        .unwrap_err();
+    assert_eq!(error.partial_stdout(), b"built\n");
+
+    // Nothing is captured by run()
+    let error = cmd!("sh", "-c", "echo x; exit 1")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert!(error.partial_stdout().is_empty());
+}
+
+/// Tests that errors propagate with `?` through several layers and keep the
+/// failing command and its stderr in the message
+#[test]
+fn test_error_propagation_with_question_mark() {
+    fn build_step() -> Result<String, crate::cmd::Error> {
+        cmd!("sh", "-c", "echo 'missing dependency' >&2; exit 2")
+            .no_echo()
+            .output()
+    }
+
+    fn build() -> Result<String, Box<dyn std::error::Error>> {
+        let output = build_step()?;
+        Ok(output)
+    }
+
+    fn release() -> Result<(), Box<dyn std::error::Error>> {
+        build()?;
+        Ok(())
+    }
+
+    let message = release().unwrap_err().to_string();
+    assert!(message.contains("exit code 2"));
+    assert!(message.contains("sh -c"));
+    assert!(message.contains("missing dependency"));
+
+    // Commands that fail without writing to stderr are still named
+    let error = cmd!("false").no_echo().run().unwrap_err();
+    assert!(error.to_string().contains("(command: false)"));
+
+    // So are those failing on the other paths that check the exit status
+    let failing = || cmd!("sh", "-c", "exit 3").no_echo();
+    let errors = [
+        failing()
+            .for_each_line(|_| std::ops::ControlFlow::Continue(()))
+            .unwrap_err(),
+        failing()
+            .spawn_with_io()
+            .unwrap()
+            .handle
+            .wait()
+            .unwrap_err(),
+        failing().reader().unwrap().wait().unwrap_err(),
+        failing().label("job").run().unwrap_err(),
+        failing().inherit_stdout_capture_stderr().unwrap_err(),
+        failing().interactive_capture().unwrap_err(),
+        failing()
+            .capture_into(Default::default())
+            .unwrap()
+            .wait()
+            .unwrap_err(),
+    ];
+    for error in errors {
+        assert!(
+            error.to_string().contains("(command: sh -c 'exit 3')"),
+            "{error}"
+        );
+    }
+}
+
+/// Tests conversion into `std::io::Error` for functions returning `io::Result`
+#[test]
+fn test_error_into_io_error() {
+    fn run_missing() -> std::io::Result<()> {
+        cmd!("nonexistent_command_12345").no_echo().run()?;
+        Ok(())
+    }
+
+    fn run_failing() -> std::io::Result<()> {
+        cmd!("sh", "-c", "echo boom >&2; exit 1")
+            .no_echo()
+            .output()?;
+        Ok(())
+    }
+
+    let error = run_missing().unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+    assert!(error.to_string().contains("nonexistent_command_12345"));
+
+    let error = run_failing().unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::Other);
+    assert!(error.to_string().contains("boom"));
+    let inner = error.get_ref().unwrap();
+    assert!(inner.downcast_ref::<crate::cmd::Error>().is_some());
+}
+
+/// Tests that the exit code of a failed command is exposed
+#[test]
+fn test_error_exit_code() {
+    let error = cmd!("sh", "-c", "exit 3").no_echo().run().unwrap_err();
+    assert_eq!(error.code(), Some(3));
+
+    let error = cmd!("sh", "-c", "exit 3").no_echo().output().unwrap_err();
+    assert_eq!(error.code(), Some(3));
+
+    // The failing stage of a pipeline is reported
+    let error = cmd!("sh", "-c", "exit 4")
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(error.code(), Some(4));
+
+    // Distinguish "no match" from real failures
+    let result = cmd!("grep", "needle").input("haystack\n").no_echo().run();
+    assert!(matches!(result, Err(e) if e.code() == Some(1)));
+
+    // No code when the command could not be spawned
+    let error = cmd!("nonexistent_command_12345")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(error.code(), None);
+}
+
+/// Tests that errors report their kind for matching without parsing messages
+#[test]
+fn test_error_kind() {
+    let error = cmd!("nonexistent_command_12345")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::SpawnFailed);
+
+    let error = cmd!("sh", "-c", "exit 3").no_echo().output().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::NonZeroExit);
+
+    let error = cmd!("false").pipe(cmd!("cat")).no_echo().run().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::NonZeroExit);
+
+    let error = cmd!("echo", "a\0b").no_echo().run().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidArgument);
+
+    let error = crate::wait_for(
+        || cmd!("true").no_echo(),
+        "never",
+        std::time::Duration::from_millis(50),
+        std::time::Duration::from_millis(10),
+    )
+    .unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::Timeout);
+
+    // The message is unchanged
+    let error = cmd!("sh", "-c", "exit 3").no_echo().run().unwrap_err();
+    assert!(error.to_string().contains("exit code"));
+}
+
+/// Tests that allow_failure() keeps non-zero exits from becoming errors
+#[test]
+fn test_allow_failure() {
+    cmd!("sh", "-c", "exit 5")
+        .allow_failure()
+        .no_echo()
+        .run()
+        .unwrap();
+
+    let output = cmd!("sh", "-c", "echo partial; exit 5")
+        .allow_failure()
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "partial\n");
+
+    // Applies to the whole pipeline
+    cmd!("sh", "-c", "exit 5")
+        .pipe(cmd!("cat"))
+        .allow_failure()
+        .no_echo()
+        .run()
+        .unwrap();
+
+    // Applies to the other ways of running a command
+    let status = cmd!("sh", "-c", "echo err >&2; exit 5")
+        .allow_failure()
+        .no_echo()
+        .inherit_stdout_capture_stderr()
+        .unwrap();
+    assert_eq!(status.code(), Some(5));
+
+    let output = cmd!("sh", "-c", "echo partial; exit 5")
+        .allow_failure()
+        .no_echo()
+        .interactive_capture()
+        .unwrap();
+    assert_eq!(output, "partial\n");
+
+    let buf = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    cmd!("sh", "-c", "echo partial; exit 5")
+        .allow_failure()
+        .no_echo()
+        .capture_into(std::sync::Arc::clone(&buf))
+        .unwrap()
+        .wait()
+        .unwrap();
+    assert_eq!(*buf.lock().unwrap(), b"partial\n");
+
+    // Spawn errors are still errors
+    let result = cmd!("nonexistent_command_12345")
+        .allow_failure()
+        .no_echo()
+        .run();
+    assert!(result.is_err());
+}
+
+/// Tests that status() reports the exit code instead of failing
+#[test]
+fn test_status() {
+    let status = cmd!("sh", "-c", "exit 5").no_echo().status().unwrap();
+    assert!(!status.success());
+    assert_eq!(status.code(), Some(5));
+
+    let status = cmd!("true").no_echo().status().unwrap();
+    assert!(status.success());
+    assert_eq!(status.code(), Some(0));
+
+    // The first failing stage determines the status of a pipeline
+    let status = cmd!("sh", "-c", "exit 3")
+        .pipe(cmd!("sh", "-c", "cat; exit 4"))
+        .no_echo()
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(3));
+
+    assert!(
+        cmd!("nonexistent_command_12345")
+            .no_echo()
+            .status()
+            .is_err()
+    );
+}
+
+/// Tests telling a command killed by a signal from a non-zero exit
+#[test]
+fn test_status_signal() {
+    let status = cmd!("sh", "-c", "kill -9 $$").no_echo().status().unwrap();
+    assert!(!status.success());
+    assert_eq!(status.signal(), Some(9));
+    assert_eq!(status.code(), None);
+
+    let status = cmd!("sh", "-c", "exit 9").no_echo().status().unwrap();
+    assert_eq!(status.signal(), None);
+    assert_eq!(status.code(), Some(9));
+}
+
+/// Tests that stderr of a failed command is kept in the error
+#[test]
+fn test_error_stderr() {
+    let error = cmd!("sh", "-c", "echo boom >&2; exit 1")
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert!(error.stderr().contains("boom"));
+    assert!(error.to_string().ends_with(": boom"));
+
+    // Only the beginning of a long stderr is displayed
+    let error = cmd!("sh", "-c", "yes boom | head -c 100000 >&2; exit 1")
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert_eq!(error.stderr().len(), 100000);
+    assert!(error.to_string().len() < 5000);
+    assert!(error.to_string().ends_with("..."));
+
+    // Nothing is captured when stderr goes to the next command
+    let error = cmd!("sh", "-c", "echo boom >&2; exit 1")
+        .pipe_stderr(cmd!("cat"))
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert!(error.stderr().is_empty());
+}
+
+/// Tests converting an error into the exit code of the failed command
+#[test]
+fn test_error_into_exit_code() {
+    let error = cmd!("sh", "-c", "exit 7").no_echo().run().unwrap_err();
+    assert_eq!(error.into_exit_code(), 7);
+
+    let error = cmd!("sh", "-c", "exit 7").no_echo().run().unwrap_err();
+    let code = std::process::ExitCode::from(error);
+    assert_eq!(code, std::process::ExitCode::from(7));
+
+    // Errors without an exit code fall back to 1
+    let error = cmd!("nonexistent_command_12345")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(error.into_exit_code(), 1);
+
+    #[cfg(unix)]
+    {
+        let error = cmd!("sh", "-c", "kill -9 $$").no_echo().run().unwrap_err();
+        assert_eq!(error.into_exit_code(), 1);
+    }
+}
@@ -30,6 +30,17 @@ fn test_large_input() {
     assert_eq!(count, 1024 * 1024);
 }
 
+/// Tests that echoing back input larger than the pipe buffer does not deadlock
+#[test]
+fn test_large_input_echoed_back() {
+    // Well beyond the typical 64KiB pipe buffer, so stdin and stdout must be
+    // serviced concurrently
+    let huge = "0123456789abcdef\n".repeat(512 * 1024);
+    let output = cmd!("cat").input(&huge).no_echo().output().unwrap();
+    assert_eq!(output.len(), huge.len());
+    assert!(output == huge);
+}
+
 /// Tests input with special characters, newlines, etc.
 #[test]
 fn test_input_with_special_characters() {
@@ -91,6 +102,18 @@ fn test_binary_data_handling() {
     assert!(output.len() >= 200); // At least most characters should pass through
 }
 
+/// Tests raw bytes passed to input() round-trip unchanged
+#[test]
+fn test_input_raw_bytes() {
+    let data: Vec<u8> = vec![b'a', 0, 0xff, 0xfe, b'\n', 0, b'z'];
+
+    let output = cmd!("cat").input(&data).no_echo().output_bytes().unwrap();
+    assert_eq!(output, data);
+
+    let count = cmd!("wc", "-c").input(&data).no_echo().output().unwrap();
+    assert_eq!(count.trim(), data.len().to_string());
+}
+
 /// Tests input/output with UTF-8 characters
 #[test]
 fn test_utf8_handling() {
@@ -387,6 +410,39 @@ fn test_input_reader() {
         .unwrap();
 
     assert_eq!(output_bytes, binary_data);
+
+    // The reader feeds the first command of a pipeline
+    let output = cmd!("cat")
+        .input_reader(Cursor::new("round trip\n"))
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "round trip\n");
+}
+
+/// Tests that a single command fed by a Reader returns the reader's contents exactly
+#[test]
+fn test_single_command_input_reader_exact() {
+    use std::io::Cursor;
+
+    // Trailing newline and blank lines must survive untouched
+    let text = "alpha\n\nbeta\n";
+    let output = cmd!("cat")
+        .input_reader(Cursor::new(text))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, text);
+
+    // More data than fits in a pipe buffer, so stdin is written while stdout is read
+    let data: Vec<u8> = (0..256 * 1024).map(|i| (i % 251) as u8).collect();
+    let output = cmd!("cat")
+        .input_reader(Cursor::new(data.clone()))
+        .no_echo()
+        .output_bytes()
+        .unwrap();
+    assert_eq!(output, data);
 }
 
 /// Tests buffered Reader input
@@ -434,6 +490,172 @@ fn test_stream_to_writer() {
     assert_eq!(binary_output, binary_input);
 }
 
+/// Tests that null-delimited, non-UTF-8 output survives unchanged
+#[test]
+fn test_output_bytes_null_delimited() {
+    // Like `find -print0` listing a file name that is not valid UTF-8
+    let expected = b"a.txt\0caf\xe9.txt\0".to_vec();
+    let output = cmd!("printf", "a.txt\\0caf\\351.txt\\0")
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .output_bytes()
+        .unwrap();
+    assert_eq!(output, expected);
+
+    let names: Vec<&[u8]> = output
+        .split(|&b| b == 0)
+        .filter(|n| !n.is_empty())
+        .collect();
+    assert_eq!(names, [&b"a.txt"[..], &b"caf\xe9.txt"[..]]);
+
+    let output = cmd!("printf", "a.txt\\0caf\\351.txt\\0")
+        .no_echo()
+        .output_bytes()
+        .unwrap();
+    assert_eq!(output, expected);
+}
+
+/// Tests reading the output of a running pipeline incrementally
+#[test]
+fn test_reader() {
+    use std::io::{BufRead, BufReader};
+
+    let mut reader = cmd!("seq", "1", "1000")
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .reader()
+        .unwrap();
+    let mut count = 0;
+    for (i, line) in BufReader::new(&mut reader).lines().enumerate() {
+        assert_eq!(line.unwrap(), (i + 1).to_string());
+        count += 1;
+    }
+    assert_eq!(count, 1000);
+    reader.wait().unwrap();
+
+    // Stopping early and killing an endless pipeline
+    let mut reader = cmd!("yes").pipe(cmd!("cat")).no_echo().reader().unwrap();
+    let first = BufReader::new(&mut reader).lines().next().unwrap().unwrap();
+    assert_eq!(first, "y");
+    reader.kill();
+    assert!(reader.wait().is_err());
+
+    // Dropping a reader without waiting kills and reaps the commands
+    let pid = std::sync::Arc::new(std::sync::Mutex::new(None));
+    let spawned = std::sync::Arc::clone(&pid);
+    let mut reader = cmd!("yes")
+        .on_spawn(move |id| *spawned.lock().unwrap() = Some(id))
+        .no_echo()
+        .reader()
+        .unwrap();
+    BufReader::new(&mut reader).lines().next().unwrap().unwrap();
+    drop(reader);
+    let pid = pid.lock().unwrap().unwrap();
+    let alive = cmd!("kill", "-0", pid.to_string())
+        .stderr_to_null()
+        .no_echo()
+        .status()
+        .unwrap()
+        .success();
+    assert!(!alive, "yes ({pid}) is still running or unreaped");
+
+    // Failures are reported with stderr on wait
+    let reader = cmd!("sh", "-c", "echo out; echo oops >&2; exit 2")
+        .no_echo()
+        .reader()
+        .unwrap();
+    let err = reader.wait().unwrap_err();
+    assert_eq!(err.code(), Some(2));
+    assert_eq!(err.stderr(), "oops\n");
+}
+
+/// Tests processing output line by line as it arrives
+#[test]
+fn test_for_each_line() {
+    use std::ops::ControlFlow;
+
+    let mut lines = Vec::new();
+    cmd!("printf", "one\\ntwo\\r\\nthree")
+        .no_echo()
+        .for_each_line(|line| {
+            lines.push(line.to_string());
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(lines, ["one", "two", "three"]);
+
+    // Breaking stops an endless command
+    let mut count = 0;
+    cmd!("yes", "line")
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .for_each_line(|line| {
+            assert_eq!(line, "line");
+            count += 1;
+            if count == 3 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+    assert_eq!(count, 3);
+
+    // A failing command is still an error
+    let result = cmd!("sh", "-c", "echo partial; exit 2")
+        .no_echo()
+        .for_each_line(|_| ControlFlow::Continue(()));
+    assert_eq!(result.unwrap_err().code(), Some(2));
+}
+
+/// Tests keeping only the last lines of a large output
+#[test]
+fn test_tail_lines() {
+    let output = cmd!("seq", "10000")
+        .tail_lines(100)
+        .no_echo()
+        .output()
+        .unwrap();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 100);
+    assert_eq!(lines[0], "9901");
+    assert_eq!(lines[99], "10000");
+
+    // Fewer lines than requested, a missing final newline, and zero lines
+    let output = cmd!("printf", "a\\nb")
+        .pipe(cmd!("cat"))
+        .tail_lines(5)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "a\nb");
+    let output = cmd!("seq", "3").tail_lines(0).no_echo().output().unwrap();
+    assert!(output.is_empty());
+}
+
+/// Tests that tee both streams the output to a writer and returns it
+#[test]
+fn test_tee() {
+    let mut buffer = Vec::new();
+    let output = cmd!("printf", "one\\ntwo\\n")
+        .pipe(cmd!("tr", "a-z", "A-Z"))
+        .no_echo()
+        .tee(&mut buffer)
+        .unwrap();
+
+    assert_eq!(output, "ONE\nTWO\n");
+    assert_eq!(String::from_utf8(buffer).unwrap(), output);
+
+    // Output written before a failure is kept in the error
+    let mut buffer = Vec::new();
+    let err = cmd!("sh", "-c", "echo partial; exit 1")
+        .no_echo()
+        .tee(&mut buffer)
+        .unwrap_err();
+    assert_eq!(err.partial_stdout(), b"partial\n");
+    assert_eq!(buffer, b"partial\n");
+}
+
 /// Tests combined Reader + Writer usage
 #[test]
 fn test_run_with_io() {
@@ -556,3 +778,499 @@ fn test_large_data_reader_writer() {
     assert_eq!(output_buffer.len(), 10240);
     assert_eq!(String::from_utf8(output_buffer).unwrap(), large_data);
 }
+
+/// Tests writing stdout and stderr to separate files without failing on non-zero exit
+#[test]
+fn test_capture_to_files() {
+    let dir = std::env::temp_dir().join(format!("scriptify_capture_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let out_path = dir.join("out.log");
+    let err_path = dir.join("err.log");
+
+    // Pre-existing contents are truncated
+    std::fs::write(&out_path, "stale contents\n").unwrap();
+
+    let status = cmd!("sh", "-c", "echo to-stdout; echo to-stderr >&2; exit 3")
+        .no_echo()
+        .capture_to_files(&out_path, &err_path)
+        .unwrap();
+
+    assert_eq!(status.code(), Some(3));
+    assert_eq!(std::fs::read_to_string(&out_path).unwrap(), "to-stdout\n");
+    assert_eq!(std::fs::read_to_string(&err_path).unwrap(), "to-stderr\n");
+
+    // Unopenable files are reported as errors
+    let result = cmd!("true")
+        .no_echo()
+        .capture_to_files(dir.join("missing/out.log"), &err_path);
+    assert!(result.is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Tests capturing output together with the rendered command line
+#[test]
+fn test_run_traced() {
+    let (output, rendered) = cmd!("sh", "-c", "echo out; echo err >&2")
+        .env("LANG", "C")
+        .no_echo()
+        .run_traced()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"out\n");
+    assert_eq!(output.stderr, b"err\n");
+    assert_eq!(rendered, "env: LANG=C sh -c 'echo out; echo err >&2'");
+
+    // Non-zero exits are reported through the status rather than as errors
+    let (output, rendered) = cmd!("sh", "-c", "exit 4")
+        .pipe(cmd!("cat"))
+        .input("data")
+        .no_echo()
+        .run_traced()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(rendered, "sh -c 'exit 4' | cat");
+
+    // Spawn failures are still errors
+    assert!(
+        cmd!("nonexistent_command_12345")
+            .no_echo()
+            .run_traced()
+            .is_err()
+    );
+}
+
+/// Tests that raw output keeps whitespace and normalized output fixes line endings
+#[test]
+fn test_output_raw_and_normalized() {
+    let raw = cmd!("printf", "  line1\\r\\nline2\\n\\n")
+        .no_echo()
+        .output_raw()
+        .unwrap();
+    assert_eq!(raw, "  line1\r\nline2\n\n");
+
+    let normalized = cmd!("printf", "  line1\\r\\nline2\\r\\n\\r\\n")
+        .no_echo()
+        .output_normalized()
+        .unwrap();
+    assert_eq!(normalized, "  line1\nline2\n\n");
+
+    // A lone carriage return is not a line ending
+    let normalized = cmd!("printf", "a\\rb\\n")
+        .pipe(cmd!("cat"))
+        .no_echo()
+        .output_normalized()
+        .unwrap();
+    assert_eq!(normalized, "a\rb\n");
+}
+
+/// Tests the non-interactive path of `interactive_capture()`
+#[test]
+fn test_interactive_capture() {
+    let output = cmd!("sh", "-c", "echo captured; echo prompt >&2")
+        .no_echo()
+        .interactive_capture()
+        .unwrap();
+    assert_eq!(output, "captured\n");
+
+    let error = cmd!("sh", "-c", "echo partial; exit 4")
+        .no_echo()
+        .interactive_capture()
+        .unwrap_err();
+    assert!(error.to_string().contains("exit code 4"));
+    assert_eq!(error.partial_stdout(), b"partial\n");
+}
+
+/// Tests that the first stage gets an empty stdin unless input is set or
+/// stdin_inherit() is used
+#[cfg(target_os = "linux")]
+#[test]
+fn test_stdin_empty_without_input() {
+    let Ok(parent_stdin) = std::fs::read_link("/proc/self/fd/0") else {
+        return;
+    };
+    let parent_stdin = parent_stdin.display().to_string();
+    let child_stdin = || cmd!("readlink", "/proc/self/fd/0").no_echo();
+
+    let output = child_stdin().output().unwrap();
+    assert_eq!(output.trim(), "/dev/null");
+
+    let output = child_stdin().pipe(cmd!("cat")).no_echo().output().unwrap();
+    assert_eq!(output.trim(), "/dev/null");
+
+    // Inheriting is opt-in and replaces input set before
+    let output = child_stdin().stdin_inherit().output().unwrap();
+    assert_eq!(output.trim(), parent_stdin);
+
+    let output = child_stdin()
+        .input("data")
+        .stdin_inherit()
+        .pipe(cmd!("cat"))
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), parent_stdin);
+}
+
+/// Tests decoding output in legacy encodings
+#[cfg(feature = "encoding")]
+#[test]
+fn test_output_with_encoding() {
+    // "café €5" in windows-1252; 0xE9 is also "é" in latin1
+    let bytes = b"caf\xe9 \x805".to_vec();
+
+    let output = cmd!("cat")
+        .input_bytes(&bytes)
+        .no_echo()
+        .output_with_encoding("windows-1252")
+        .unwrap();
+    assert_eq!(output, "café €5");
+
+    let output = cmd!("cat")
+        .input_bytes(&bytes)
+        .no_echo()
+        .output_with_encoding("Latin1")
+        .unwrap();
+    assert_eq!(output, "café \u{80}5");
+
+    let output = cmd!("echo", "café")
+        .no_echo()
+        .output_with_encoding(" UTF-8 ")
+        .unwrap();
+    assert_eq!(output, "café\n");
+
+    // An unknown label is rejected before anything runs
+    let marker = std::env::temp_dir().join(format!("scriptify_encoding_{}", std::process::id()));
+    let error = cmd!("touch", &marker)
+        .no_echo()
+        .output_with_encoding("ebcdic")
+        .unwrap_err();
+    assert!(error.to_string().contains("Unsupported encoding: ebcdic"));
+    assert!(!marker.exists());
+}
+
+/// Tests capturing output together with a non-zero exit status
+#[test]
+fn test_output_with_status() {
+    let (output, status) = cmd!("sh", "-c", "echo 'M file.rs'; exit 2")
+        .no_echo()
+        .output_with_status()
+        .unwrap();
+    assert_eq!(output, "M file.rs\n");
+    assert!(!status.success());
+    assert_eq!(status.code(), Some(2));
+
+    let (output, status) = cmd!("echo", "clean")
+        .pipe(cmd!("tr", "a-z", "A-Z"))
+        .no_echo()
+        .output_with_status()
+        .unwrap();
+    assert_eq!(output, "CLEAN\n");
+    assert!(status.success());
+    assert_eq!(status.code(), Some(0));
+
+    assert!(
+        cmd!("nonexistent_command_12345")
+            .no_echo()
+            .output_with_status()
+            .is_err()
+    );
+}
+
+/// Tests redirecting stdout to a file
+#[test]
+fn test_stdout_to_file() {
+    let dir = std::env::temp_dir().join(format!("scriptify_stdout_to_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("out.log");
+    std::fs::write(&path, "stale contents\n").unwrap();
+
+    cmd!("echo", "first")
+        .stdout_to(&path)
+        .no_echo()
+        .run()
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\n");
+
+    cmd!("echo", "second")
+        .stdout_append(&path)
+        .no_echo()
+        .run()
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "first\nsecond\n");
+
+    // Redirected output is not captured
+    let output = cmd!("echo", "third")
+        .stdout_to(&path)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert!(output.is_empty());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "third\n");
+
+    // The redirection is part of the echoed command
+    let rendered = cmd!("echo", "x")
+        .stdout_append("out.log")
+        .into_pipeline()
+        .render(false);
+    assert_eq!(rendered, "echo x >> out.log");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Tests discarding stdout and stderr
+#[test]
+fn test_to_null() {
+    let (output, rendered) = cmd!("sh", "-c", "echo kept; echo noise >&2")
+        .stderr_to_null()
+        .no_echo()
+        .run_traced()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"kept\n");
+    assert!(output.stderr.is_empty());
+    assert_eq!(rendered, "sh -c 'echo kept; echo noise >&2' 2> /dev/null");
+
+    let (output, rendered) = cmd!("sh", "-c", "echo noise; echo kept >&2")
+        .stdout_to_null()
+        .no_echo()
+        .run_traced()
+        .unwrap();
+    assert!(output.stdout.is_empty());
+    assert_eq!(output.stderr, b"kept\n");
+    assert!(rendered.ends_with(" > /dev/null"));
+}
+
+/// Tests that redirects apply when stdin or stdout is inherited
+#[test]
+fn test_redirects_with_inherited_streams() {
+    let dir =
+        std::env::temp_dir().join(format!("scriptify_redirect_inherit_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("out.log");
+
+    let output = cmd!("echo", "to file")
+        .stdout_to(&path)
+        .no_echo()
+        .interactive_capture()
+        .unwrap();
+    assert!(output.is_empty());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "to file\n");
+
+    cmd!("echo", "inherited")
+        .stdout_to(&path)
+        .no_echo()
+        .inherit_stdout_capture_stderr()
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "inherited\n");
+
+    let error = cmd!("sh", "-c", "echo noise >&2; exit 3")
+        .stderr_to_null()
+        .no_echo()
+        .inherit_stdout_capture_stderr()
+        .unwrap_err();
+    assert_eq!(error.code(), Some(3));
+    assert!(error.stderr().is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Tests feeding a file to a command as its stdin
+#[test]
+fn test_stdin_from_file() {
+    let dir = std::env::temp_dir().join(format!("scriptify_stdin_from_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("names.txt");
+    std::fs::write(&path, "carol\nalice\nbob\n").unwrap();
+
+    let count = cmd!("wc", "-l")
+        .stdin_from_file(&path)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(count.trim(), "3");
+
+    let sorted = cmd!("sort")
+        .stdin_from_file(&path)
+        .pipe(cmd!("head", "-n", "1"))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(sorted, "alice\n");
+
+    // The last input method wins
+    let output = cmd!("cat")
+        .input("ignored")
+        .stdin_from_file(&path)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "carol\nalice\nbob\n");
+    let output = cmd!("cat")
+        .stdin_from_file(&path)
+        .input("from memory")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output, "from memory");
+
+    // The redirection is part of the echoed command
+    let rendered = cmd!("sort")
+        .stdin_from_file("names.txt")
+        .pipe(cmd!("uniq"))
+        .render(false);
+    assert_eq!(rendered, "sort < names.txt | uniq");
+
+    // A missing file is reported as an error
+    let result = cmd!("cat")
+        .stdin_from_file(dir.join("missing.txt"))
+        .no_echo()
+        .run();
+    assert!(result.is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Tests that `run()` hands this process's stdout and stderr to the command
+#[cfg(target_os = "linux")]
+#[test]
+fn test_run_inherits_output() {
+    let dir = std::env::temp_dir().join(format!("scriptify_run_inherit_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let out = dir.join("fds.txt");
+
+    // The shell reports what its own stdout and stderr are connected to
+    let script = format!(
+        "fds=$(readlink /proc/$$/fd/1 /proc/$$/fd/2); echo \"$fds\" > '{}'",
+        out.display()
+    );
+    let own_fds = [
+        std::fs::read_link("/proc/self/fd/1").unwrap(),
+        std::fs::read_link("/proc/self/fd/2").unwrap(),
+    ];
+    let read_fds = || -> Vec<std::path::PathBuf> {
+        std::fs::read_to_string(&out)
+            .unwrap()
+            .lines()
+            .map(std::path::PathBuf::from)
+            .collect()
+    };
+
+    cmd!("sh", "-c", &script).no_echo().run().unwrap();
+    assert_eq!(read_fds(), own_fds);
+
+    cmd!("echo")
+        .pipe(cmd!("sh", "-c", &script))
+        .no_echo()
+        .run()
+        .unwrap();
+    assert_eq!(read_fds(), own_fds);
+
+    // Capturing methods use pipes, unless `inherit()` is requested
+    cmd!("sh", "-c", &script).no_echo().output().unwrap();
+    assert_ne!(read_fds(), own_fds);
+
+    let output = cmd!("sh", "-c", &script)
+        .inherit()
+        .no_echo()
+        .output()
+        .unwrap();
+    assert!(output.is_empty());
+    assert_eq!(read_fds(), own_fds);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Tests streaming a file through a multi-stage pipeline
+#[test]
+fn test_pipeline_input_file() {
+    let dir = std::env::temp_dir().join(format!("scriptify_input_file_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("app.log");
+    std::fs::write(&path, "INFO start\nERROR disk\nINFO step\nERROR net\n").unwrap();
+
+    let count = cmd!("grep", "ERROR")
+        .pipe(cmd!("wc", "-l"))
+        .input_file(&path)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(count.trim(), "2");
+
+    let rendered = cmd!("grep", "ERROR")
+        .pipe(cmd!("wc", "-l"))
+        .input_file("app.log")
+        .render(false);
+    assert_eq!(rendered, "grep ERROR < app.log | wc -l");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Tests capturing stdout and stderr separately
+#[test]
+fn test_output_split() {
+    let (stdout, stderr) = cmd!("sh", "-c", "echo out; echo err >&2")
+        .no_echo()
+        .output_split()
+        .unwrap();
+    assert_eq!(stdout, "out\n");
+    assert_eq!(stderr, "err\n");
+
+    // Large output on both streams doesn't deadlock
+    let (stdout, stderr) = cmd!(
+        "sh",
+        "-c",
+        "yes o | head -c 200000; yes e | head -c 200000 >&2"
+    )
+    .no_echo()
+    .output_split()
+    .unwrap();
+    assert_eq!(stdout.len(), 200000);
+    assert_eq!(stderr.len(), 200000);
+
+    let error = cmd!("sh", "-c", "echo partial; echo failed >&2; exit 1")
+        .no_echo()
+        .output_split()
+        .unwrap_err();
+    assert_eq!(error.code(), Some(1));
+    assert_eq!(error.partial_stdout(), b"partial\n");
+    assert_eq!(error.stderr(), "failed\n");
+}
+
+/// Tests that `stdin_null()` gives the command EOF in place of any other stdin
+#[test]
+fn test_stdin_null() {
+    // It replaces inheriting stdin requested before
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let _ = tx.send(cmd!("cat").stdin_inherit().stdin_null().no_echo().output());
+    });
+    let output = rx
+        .recv_timeout(std::time::Duration::from_secs(10))
+        .expect("cat with stdin_null() did not finish")
+        .unwrap();
+    assert!(output.is_empty());
+
+    // It replaces the pipe that spawn_with_io() would otherwise give
+    let spawn = cmd!("cat").no_echo().spawn_with_io().unwrap();
+    assert!(spawn.stdin.is_some());
+    drop(spawn.stdin);
+    spawn.handle.wait().unwrap();
+    let spawn = cmd!("cat").stdin_null().no_echo().spawn_with_io().unwrap();
+    assert!(spawn.stdin.is_none());
+    spawn.handle.wait().unwrap();
+
+    // It replaces input set before
+    let output = cmd!("cat")
+        .input("ignored")
+        .stdin_null()
+        .no_echo()
+        .output()
+        .unwrap();
+    assert!(output.is_empty());
+
+    let rendered = cmd!("cat")
+        .stdin_null()
+        .pipe(cmd!("wc", "-c"))
+        .render(false);
+    assert_eq!(rendered, "cat < /dev/null | wc -c");
+}
@@ -6,14 +6,30 @@
 // Re-export items needed by test modules
 use super::*;
 
-// Test modules
+// Test modules; most run Unix tools such as `sh`, `cat` and `grep`
+#[cfg(unix)]
 mod basic;
+#[cfg(unix)]
 mod concurrency;
+#[cfg(unix)]
 mod environment;
+#[cfg(unix)]
 mod error_handling;
+#[cfg(unix)]
 mod input_output;
 
+#[cfg(unix)]
 mod no_echo;
+#[cfg(unix)]
 mod pipeline;
 mod quoting;
+#[cfg(unix)]
+mod record;
+#[cfg(unix)]
 mod security;
+#[cfg(all(unix, feature = "tracing"))]
+mod tracing_echo;
+#[cfg(unix)]
+mod wait;
+#[cfg(windows)]
+mod windows;
@@ -287,6 +287,60 @@ fn test_pipe_mode_combinations() {
     assert!(line_count >= 1);
 }
 
+/// Tests that only the last stage's status matters without pipefail
+#[test]
+fn test_pipefail() {
+    let failing_first = || {
+        cmd!("sh", "-c", "echo data; exit 3")
+            .pipe(cmd!("cat"))
+            .no_echo()
+    };
+
+    // By default any failing stage fails the pipeline
+    let error = failing_first().output().unwrap_err();
+    assert_eq!(error.code(), Some(3));
+    assert_eq!(failing_first().status().unwrap().code(), Some(3));
+    assert!(failing_first().pipefail(true).run().is_err());
+
+    // Without pipefail the last stage decides
+    let output = failing_first().pipefail(false).output().unwrap();
+    assert_eq!(output, "data\n");
+    failing_first().pipefail(false).run().unwrap();
+    let status = failing_first().pipefail(false).status().unwrap();
+    assert!(status.success());
+    let mut sink = Vec::new();
+    failing_first()
+        .pipefail(false)
+        .stream_to(&mut sink)
+        .unwrap();
+    assert_eq!(sink, b"data\n");
+
+    // A failing last stage still fails the pipeline
+    let error = cmd!("echo", "data")
+        .pipe(cmd!("sh", "-c", "cat; exit 4"))
+        .pipefail(false)
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert_eq!(error.code(), Some(4));
+
+    // Without pipefail the reported status is the last stage's, even when an
+    // earlier stage failed too
+    let status = cmd!("sh", "-c", "exit 3")
+        .pipe(cmd!("sh", "-c", "cat; exit 4"))
+        .pipefail(false)
+        .no_echo()
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(4));
+    let (output, status) = failing_first()
+        .pipefail(false)
+        .output_with_status()
+        .unwrap();
+    assert_eq!(output, "data\n");
+    assert!(status.success());
+}
+
 /// Tests pipeline with no connections
 #[test]
 fn test_empty_pipeline() {
@@ -294,6 +348,12 @@ fn test_empty_pipeline() {
         connections: vec![],
         input: None,
         suppress_echo: true,
+        allow_failure: false,
+        pipefail: true,
+        tail_lines: None,
+        tees: Vec::new(),
+        buffer_size: crate::cmd::pipeline::DEFAULT_BUFFER_SIZE,
+        current_dir: None,
     };
     let result = pipeline.output().unwrap();
     assert!(result.is_empty());
@@ -392,3 +452,273 @@ fn test_pipe_both_mixed_output() {
     assert_eq!(lines[0], "ERR:message2");
     assert_eq!(lines[1], "OUT:message1");
 }
+
+/// Tests collapsing a one-stage pipeline back into a `Cmd`
+#[test]
+fn test_into_single_cmd() {
+    let cmd = cmd!("echo", "hi")
+        .into_pipeline()
+        .no_echo()
+        .into_single_cmd()
+        .unwrap();
+    assert_eq!(cmd.program, "echo");
+    assert!(cmd.suppress_echo);
+    assert_eq!(cmd.output().unwrap().trim(), "hi");
+
+    // Multiple stages stay a pipeline
+    let pipeline = cmd!("echo", "hi").pipe(cmd!("cat"));
+    let pipeline = pipeline.into_single_cmd().unwrap_err();
+    assert_eq!(pipeline.connections.len(), 2);
+
+    // Pipeline input can't be represented on a `Cmd`
+    let pipeline = cmd!("cat").input("data").into_single_cmd().unwrap_err();
+    assert_eq!(pipeline.no_echo().output().unwrap(), "data");
+}
+
+/// Tests selecting the captured stream of the final stage independently of pipe modes
+#[test]
+fn test_output_bytes_of() {
+    let make = || {
+        cmd!("echo", "data").pipe(cmd!(
+            "sh",
+            "-c",
+            "read line; echo \"out:$line\"; echo \"err:$line\" >&2"
+        ))
+    };
+
+    let stdout = make().no_echo().output_bytes_of(PipeMode::Stdout).unwrap();
+    assert_eq!(stdout, b"out:data\n");
+
+    let stderr = make().no_echo().output_bytes_of(PipeMode::Stderr).unwrap();
+    assert_eq!(stderr, b"err:data\n");
+
+    let both =
+        String::from_utf8(make().no_echo().output_bytes_of(PipeMode::Both).unwrap()).unwrap();
+    assert!(both.contains("out:data\n"));
+    assert!(both.contains("err:data\n"));
+
+    // Single commands and failures
+    let stderr = cmd!("sh", "-c", "echo report >&2")
+        .no_echo()
+        .output_bytes_of(PipeMode::Stderr)
+        .unwrap();
+    assert_eq!(stderr, b"report\n");
+    assert!(
+        cmd!("sh", "-c", "echo x >&2; exit 1")
+            .no_echo()
+            .output_bytes_of(PipeMode::Stderr)
+            .is_err()
+    );
+}
+
+/// Tests inserting a stage in the middle of a pipeline
+#[test]
+fn test_insert_at() {
+    let output = cmd!("printf", "apple\\nbanana\\navocado\\n")
+        .pipe(cmd!("wc", "-l"))
+        .insert_at(1, cmd!("grep", "^a"), PipeMode::Stdout)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim(), "2");
+
+    // Inserting at the front and at the end
+    let pipeline = cmd!("grep", "b")
+        .pipe(cmd!("tr", "a-z", "A-Z"))
+        .insert_at(0, cmd!("printf", "abc\\nxyz\\n"), PipeMode::Stdout)
+        .insert_at(3, cmd!("cat"), PipeMode::Stdout);
+    assert_eq!(pipeline.connections.len(), 4);
+    assert_eq!(pipeline.no_echo().output().unwrap(), "ABC\n");
+}
+
+/// Tests that inserting past the end of a pipeline panics
+#[test]
+#[should_panic(expected = "out of range")]
+fn test_insert_at_out_of_range() {
+    let _ = cmd!("echo")
+        .pipe(cmd!("cat"))
+        .insert_at(3, cmd!("wc"), PipeMode::Stdout);
+}
+
+/// Tests that per-stage options of the first command survive pipeline construction
+#[test]
+fn test_first_stage_redirect_survives_pipe() {
+    use crate::cmd::types::Redirect;
+
+    let dir = std::env::temp_dir().join(format!("scriptify_stage_opts_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let log = dir.join("first.log");
+
+    let mut first = cmd!("sh", "-c", "echo to-file; echo to-pipe >&2").env("STAGE", "first");
+    first.stdout_redirect = Some(Redirect::File {
+        path: log.clone(),
+        append: false,
+    });
+
+    let pipeline = first.pipe_stderr(cmd!("tr", "a-z", "A-Z"));
+    let (stage, _) = &pipeline.connections[0];
+    assert!(stage.stdout_redirect.is_some());
+    assert_eq!(stage.envs.len(), 1);
+
+    let output = pipeline.no_echo().output().unwrap();
+    assert_eq!(output, "TO-PIPE\n");
+    assert_eq!(std::fs::read_to_string(&log).unwrap(), "to-file\n");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Tests collecting the exit status of each pipeline stage
+#[test]
+fn test_output_bytes_split() {
+    let (output, statuses) = cmd!("printf", "b\\na\\n")
+        .pipe(cmd!("sh", "-c", "cat; exit 4"))
+        .pipe(cmd!("sort"))
+        .no_echo()
+        .output_bytes_split()
+        .unwrap();
+    assert_eq!(output, b"a\nb\n");
+    assert_eq!(statuses.len(), 3);
+    assert!(statuses[0].success());
+    assert_eq!(statuses[1].code(), Some(4));
+    assert!(statuses[2].success());
+    assert_eq!(
+        statuses.iter().position(|status| !status.success()),
+        Some(1)
+    );
+}
+
+/// Tests copying the output of a stage to a writer while piping it on
+#[test]
+fn test_tee_to() {
+    use crate::test_util::SharedBuf;
+
+    let buf = SharedBuf::default();
+    let count = cmd!("seq", "1", "20000")
+        .tee_to(buf.clone())
+        .pipe(cmd!("wc", "-l"))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(count.trim(), "20000");
+    let copied = buf.contents();
+    assert_eq!(copied.lines().count(), 20000);
+    assert!(copied.ends_with("19999\n20000\n"));
+
+    // The tee stays on its stage when a stage is inserted before it
+    let buf = SharedBuf::default();
+    let count = cmd!("tr", "a-z", "A-Z")
+        .tee_to(buf.clone())
+        .pipe(cmd!("wc", "-c"))
+        .insert_at(0, cmd!("echo", "abc"), PipeMode::Stdout)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(count.trim(), "4");
+    assert_eq!(buf.contents(), "ABC\n");
+
+    // The tee needs a command to pipe into
+    let result = cmd!("echo", "x")
+        .tee_to(SharedBuf::default())
+        .no_echo()
+        .output();
+    assert!(result.unwrap_err().to_string().contains("tee_to()"));
+}
+
+/// Tests that large data passes through intact with custom buffer sizes
+#[test]
+fn test_buffer_size() {
+    use std::io::Cursor;
+
+    let data: Vec<u8> = (0..3 * 1024 * 1024).map(|i| (i % 251) as u8).collect();
+    for size in [1, 1000, 1024 * 1024] {
+        let output = cmd!("cat")
+            .pipe(cmd!("cat"))
+            .input_reader(Cursor::new(data.clone()))
+            .buffer_size(size)
+            .no_echo()
+            .output_bytes()
+            .unwrap();
+        assert!(output == data, "buffer size {size}");
+    }
+
+    let mut streamed = Vec::new();
+    cmd!("cat")
+        .buffer_size(0)
+        .input_bytes(&data)
+        .no_echo()
+        .stream_to(&mut streamed)
+        .unwrap();
+    assert!(streamed == data);
+}
+
+/// Tests a working directory shared by all stages of a pipeline
+#[test]
+fn test_pipeline_current_dir() {
+    let dir = std::env::temp_dir().join(format!("scriptify_pipeline_cwd_{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    for name in ["a", "b", "c"] {
+        std::fs::write(dir.join(name), "").unwrap();
+    }
+
+    let count = cmd!("ls")
+        .pipe(cmd!("wc", "-l"))
+        .current_dir(&dir)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(count.trim(), "4");
+
+    // A stage's own directory takes precedence
+    let output = cmd!("pwd")
+        .pipe(cmd!("sh", "-c", "cat; pwd").current_dir("/"))
+        .current_dir(&dir)
+        .no_echo()
+        .output()
+        .unwrap();
+    let canonical = dir.canonicalize().unwrap();
+    assert_eq!(output, format!("{}\n/\n", canonical.display()));
+
+    // The shared directory is echoed once
+    let rendered = cmd!("ls")
+        .pipe(cmd!("wc", "-l"))
+        .pipe(cmd!("cat").current_dir("/"))
+        .current_dir("/tmp")
+        .render(false);
+    assert_eq!(rendered, "cd: /tmp ls | wc -l | cd: / cat");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Tests passing pipeline output as arguments with `xargs()`
+#[test]
+fn test_xargs() {
+    let output = cmd!("echo", "a b\nc")
+        .xargs(cmd!("echo", "args:"))
+        .output()
+        .unwrap();
+    assert_eq!(output, "args: a b c\n");
+
+    // Batches run the command once per group of items
+    let output = cmd!("printf", "1 2 3 4 5")
+        .pipe(cmd!("cat"))
+        .xargs(cmd!("echo"))
+        .batch(2)
+        .output()
+        .unwrap();
+    assert_eq!(output, "1 2\n3 4\n5\n");
+
+    // Null-delimited items keep their spaces
+    let output = cmd!("printf", "first file\\0second\\0")
+        .xargs(cmd!("printf", "[%s]"))
+        .null()
+        .output()
+        .unwrap();
+    assert_eq!(output, "[first file][second]");
+
+    // No items, no run
+    let output = cmd!("true").xargs(cmd!("echo", "ran")).output().unwrap();
+    assert!(output.is_empty());
+
+    // A failing run is an error
+    assert!(cmd!("echo", "x").xargs(cmd!("false")).run().is_err());
+}
@@ -171,6 +171,7 @@ fn test_quote_argument_mixed_everything() {
 }
 
 /// Tests that quoting works correctly in actual command execution
+#[cfg(unix)]
 #[test]
 fn test_quote_argument_integration() {
     // Test that arguments with spaces work correctly in real command execution
@@ -202,6 +203,7 @@ fn test_quote_argument_integration() {
 }
 
 /// Tests quoting behavior in pipeline commands
+#[cfg(unix)]
 #[test]
 fn test_quote_argument_pipeline_integration() {
     // Test that arguments with special characters work in pipelines
@@ -222,6 +224,7 @@ fn test_quote_argument_pipeline_integration() {
 }
 
 /// Tests quoting with command arguments that could be dangerous if not properly quoted
+#[cfg(unix)]
 #[test]
 fn test_quote_argument_security_integration() {
     // Test that potentially dangerous arguments are safely handled
@@ -239,3 +242,63 @@ fn test_quote_argument_security_integration() {
         assert_eq!(result.trim(), arg, "Failed to safely handle: {}", arg);
     }
 }
+
+/// Tests formatting commands as the echoed command line
+#[test]
+fn test_display() {
+    assert_eq!(cmd!("echo", "a b").to_string(), "echo 'a b'");
+    assert_eq!(cmd!("echo", "it's").to_string(), "echo \"it's\"");
+    assert_eq!(cmd!("echo", "").to_string(), "echo \"\"");
+
+    let with_context = cmd!("make", "all")
+        .current_dir("my project")
+        .env("CC", "gcc -O2");
+    assert_eq!(
+        with_context.to_string(),
+        "cd: 'my project' env: CC='gcc -O2' make all"
+    );
+
+    let pipeline = cmd!("ls").pipe(cmd!("grep", "foo bar"));
+    assert_eq!(pipeline.to_string(), "ls | grep 'foo bar'");
+}
+
+/// Tests that parsing groups quoted words into single arguments
+#[test]
+fn test_parse_quoted_words() {
+    let cmd = Cmd::parse(r#"echo "a b" 'c d'"#).unwrap();
+    assert_eq!(cmd.program, "echo");
+    assert_eq!(cmd.args, ["a b", "c d"]);
+}
+
+/// Tests that parsing keeps unquoted input split on whitespace
+#[test]
+fn test_parse_unquoted() {
+    let cmd = Cmd::parse("  ls   -l\t-a ").unwrap();
+    assert_eq!(cmd.program, "ls");
+    assert_eq!(cmd.args, ["-l", "-a"]);
+}
+
+/// Tests backslash escapes inside and outside of quotes
+#[test]
+fn test_parse_escapes() {
+    let cmd = Cmd::parse(r#"touch my\ file "say \"hi\"" 'no\escape' a\\b"#).unwrap();
+    assert_eq!(cmd.args, ["my file", "say \"hi\"", "no\\escape", "a\\b"]);
+
+    let cmd = Cmd::parse(r#"echo '' "" x"#).unwrap();
+    assert_eq!(cmd.args, ["", "", "x"]);
+
+    // A trailing backslash is kept rather than becoming an empty argument
+    let cmd = Cmd::parse(r"echo a \").unwrap();
+    assert_eq!(cmd.args, ["a", "\\"]);
+    let cmd = Cmd::parse(r"echo a\").unwrap();
+    assert_eq!(cmd.args, ["a\\"]);
+}
+
+/// Tests that malformed command lines are rejected
+#[test]
+fn test_parse_errors() {
+    assert!(Cmd::parse("").is_err());
+    assert!(Cmd::parse("   ").is_err());
+    assert!(Cmd::parse("echo 'oops").is_err());
+    assert!(Cmd::parse(r#"echo "oops"#).is_err());
+}
@@ -0,0 +1,71 @@
+//! Command recording tests.
+//!
+//! Tests for `record`, which captures the commands a closure would run
+//! instead of executing them, for plan/preview workflows.
+
+use crate::cmd;
+use crate::cmd::record;
+
+/// Tests that commands are recorded in order and not executed
+#[test]
+fn test_record_collects_commands() {
+    let marker = std::env::temp_dir().join(format!("scriptify_record_{}", std::process::id()));
+    let _ = std::fs::remove_file(&marker);
+
+    let plan = record(|| {
+        cmd!("touch", &marker).run().unwrap();
+        let output = cmd!("echo", "hello world").output().unwrap();
+        assert_eq!(output, "");
+        cmd!("cat", "log.txt")
+            .pipe(cmd!("grep", "error"))
+            .input("ignored")
+            .run()
+            .unwrap();
+    });
+
+    assert!(!marker.exists(), "recorded commands must not be executed");
+    assert_eq!(plan.len(), 3);
+    assert_eq!(plan[1].rendered, "echo 'hello world'");
+    assert_eq!(plan[2].rendered, "cat log.txt | grep error");
+    assert_eq!(plan[2].commands.len(), 2);
+    assert_eq!(plan[2].commands[1].program, "grep");
+}
+
+/// Tests that recording is scoped to the closure and the current thread
+#[test]
+fn test_record_is_scoped() {
+    let plan = record(|| {
+        let handle = std::thread::spawn(|| cmd!("echo", "real").no_echo().output().unwrap());
+        assert_eq!(handle.join().unwrap().trim(), "real");
+
+        let nested = record(|| cmd!("echo", "inner").run().unwrap());
+        assert_eq!(nested.len(), 1);
+
+        cmd!("echo", "outer").run().unwrap();
+    });
+    assert_eq!(plan.len(), 1);
+    assert_eq!(plan[0].rendered, "echo outer");
+
+    // Outside the closure commands execute normally again
+    assert_eq!(
+        cmd!("echo", "after").no_echo().output().unwrap().trim(),
+        "after"
+    );
+}
+
+/// Tests that recording stops even if the closure panics
+#[test]
+fn test_record_restores_after_panic() {
+    let result = std::panic::catch_unwind(|| {
+        record(|| {
+            cmd!("echo", "recorded").run().unwrap();
+            panic!("failed while recording");
+        })
+    });
+    assert!(result.is_err());
+    assert!(!record::is_recording());
+    assert_eq!(
+        cmd!("echo", "after").no_echo().output().unwrap().trim(),
+        "after"
+    );
+}
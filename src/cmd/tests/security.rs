@@ -64,6 +64,29 @@ fn test_env_var_injection() {
     }
 }
 
+/// Tests that an interior NUL byte is reported before spawning, naming the argument
+#[test]
+fn test_interior_nul_reported_clearly() {
+    let error = cmd!("echo", "fine", "bad\0arg")
+        .no_echo()
+        .run()
+        .unwrap_err();
+    assert!(error.message.contains("argument 2"));
+    assert!(error.message.contains("bad\\0arg"));
+    assert!(error.message.contains("NUL"));
+
+    let error = cmd!("ec\0ho").no_echo().output().unwrap_err();
+    assert!(error.message.contains("Invalid program name"));
+
+    // A later stage with a NUL byte prevents the whole pipeline from starting
+    let error = cmd!("echo", "data")
+        .pipe(cmd!("grep", "a\0b"))
+        .no_echo()
+        .output()
+        .unwrap_err();
+    assert!(error.message.contains("argument 1 of grep"));
+}
+
 /// Tests null byte and special character injection
 #[test]
 fn test_special_char_injection() {
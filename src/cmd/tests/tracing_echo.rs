@@ -0,0 +1,73 @@
+//! Tracing integration tests.
+//!
+//! Tests that command echoes are emitted as `tracing` events with structured
+//! fields when the `tracing` feature is enabled.
+
+use crate::cmd;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+
+#[derive(Default)]
+struct Fields(HashMap<String, String>);
+
+/// Subscriber that records the fields of every event
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<HashMap<String, String>>>>);
+
+impl Visit for Fields {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), format!("{value:?}"));
+    }
+}
+
+impl Subscriber for Capture {
+    fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+    }
+
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        self.0.lock().unwrap().push(fields.0);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+/// Tests that an echoed command becomes an event with program, args and cwd
+#[test]
+fn test_echo_emits_tracing_event() {
+    let capture = Capture::default();
+    tracing::subscriber::with_default(capture.clone(), || {
+        cmd!("echo", "hello world")
+            .current_dir("/tmp")
+            .pipe(cmd!("cat"))
+            .run()
+            .unwrap();
+        cmd!("echo", "quiet").no_echo().run().unwrap();
+    });
+
+    let events = capture.0.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    let fields = &events[0];
+    assert_eq!(fields["program"], "echo");
+    assert_eq!(fields["args"], "'hello world'");
+    assert_eq!(fields["cwd"], "/tmp");
+    assert!(fields["command"].contains("| cat"));
+    assert!(fields["message"].starts_with("cmd "));
+}
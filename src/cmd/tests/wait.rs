@@ -0,0 +1,65 @@
+//! Readiness polling tests.
+//!
+//! Tests for `wait_for`, which re-runs a command until its output
+//! contains a token or a timeout elapses.
+
+use crate::cmd;
+use crate::cmd::wait_for;
+use std::time::{Duration, Instant};
+
+/// Tests that polling returns as soon as the output contains the needle
+#[test]
+fn test_wait_for_immediate_match() {
+    let output = wait_for(
+        || cmd!("echo", "service ready").no_echo(),
+        "ready",
+        Duration::from_secs(5),
+        Duration::from_millis(10),
+    )
+    .unwrap();
+    assert_eq!(output.trim(), "service ready");
+}
+
+/// Tests that failing attempts are retried until the command succeeds
+#[test]
+fn test_wait_for_retries_until_ready() {
+    let marker = std::env::temp_dir().join(format!("scriptify_wait_{}", std::process::id()));
+    let _ = std::fs::remove_file(&marker);
+
+    let marker_clone = marker.clone();
+    let writer = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(200));
+        std::fs::write(&marker_clone, "up").unwrap();
+    });
+
+    let output = wait_for(
+        || cmd!("cat", &marker).no_echo(),
+        "up",
+        Duration::from_secs(5),
+        Duration::from_millis(20),
+    )
+    .unwrap();
+    assert_eq!(output, "up");
+
+    writer.join().unwrap();
+    std::fs::remove_file(&marker).unwrap();
+}
+
+/// Tests that polling gives up after the timeout with a descriptive error
+#[test]
+fn test_wait_for_timeout() {
+    let start = Instant::now();
+    let result = wait_for(
+        || cmd!("echo", "starting").no_echo(),
+        "ready",
+        Duration::from_millis(200),
+        Duration::from_millis(50),
+    );
+    let elapsed = start.elapsed();
+
+    let error = result.unwrap_err();
+    assert!(error.message.contains("Timed out"));
+    assert!(error.message.contains("\"ready\""));
+    assert!(elapsed >= Duration::from_millis(200));
+    assert!(elapsed < Duration::from_secs(5));
+}
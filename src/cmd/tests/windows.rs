@@ -0,0 +1,65 @@
+//! Windows command execution tests.
+//!
+//! Counterparts of the basic, pipeline and error handling tests that use
+//! `cmd /C` built-ins instead of Unix tools.
+
+use crate::cmd;
+
+/// Tests running a command and capturing its output
+#[test]
+fn test_cmd_echo() {
+    let output = cmd!("cmd", "/C", "echo", "hello")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim_end(), "hello");
+
+    cmd!("cmd", "/C", "echo", "hello").no_echo().run().unwrap();
+}
+
+/// Tests piping between commands and feeding input
+#[test]
+fn test_cmd_pipeline() {
+    let output = cmd!("cmd", "/C", "echo", "apple")
+        .pipe(cmd!("findstr", "apple"))
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim_end(), "apple");
+
+    let output = cmd!("findstr", "b")
+        .input("a\r\nb\r\nc\r\n")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim_end(), "b");
+}
+
+/// Tests that a non-zero exit is reported with its code
+#[test]
+fn test_cmd_exit_code() {
+    let error = cmd!("cmd", "/C", "exit", "3").no_echo().run().unwrap_err();
+    assert_eq!(error.code(), Some(3));
+
+    let status = cmd!("cmd", "/C", "exit", "3").no_echo().status().unwrap();
+    assert_eq!(status.code(), Some(3));
+}
+
+/// Tests environment variables and the working directory
+#[test]
+fn test_cmd_env_and_dir() {
+    let output = cmd!("cmd", "/C", "echo", "%SCRIPTIFY_TEST%")
+        .env("SCRIPTIFY_TEST", "value")
+        .no_echo()
+        .output()
+        .unwrap();
+    assert_eq!(output.trim_end(), "value");
+
+    let dir = std::env::temp_dir();
+    let output = cmd!("cmd", "/C", "cd")
+        .current_dir(&dir)
+        .no_echo()
+        .output()
+        .unwrap();
+    assert!(!output.trim_end().is_empty());
+}
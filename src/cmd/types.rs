@@ -1,9 +1,14 @@
 //! Type definitions for command execution and piping.
 
 use std::ffi::OsString;
-use std::io::Read;
+use std::io::{Read, Write};
 use std::path::PathBuf;
-use std::process::Child;
+use std::process::{Child, ExitStatus};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use super::status::Status;
 
 /// Input source for commands - either bytes in memory or a streaming reader.
 pub(crate) enum CmdInput {
@@ -11,6 +16,12 @@ pub(crate) enum CmdInput {
     Bytes(Vec<u8>),
     /// Streaming reader (boxed for object safety)
     Reader(Box<dyn Read + Send>),
+    /// File handed to the first command as its stdin
+    File(PathBuf),
+    /// No input at all: the first command reads from the null device
+    Null,
+    /// The first command reads this process's stdin
+    Inherit,
 }
 
 impl std::fmt::Debug for CmdInput {
@@ -21,29 +32,98 @@ impl std::fmt::Debug for CmdInput {
                 .field(&format!("{} bytes", bytes.len()))
                 .finish(),
             CmdInput::Reader(_) => f.debug_tuple("Reader").field(&"<reader>").finish(),
+            CmdInput::File(path) => f.debug_tuple("File").field(path).finish(),
+            CmdInput::Null => f.write_str("Null"),
+            CmdInput::Inherit => f.write_str("Inherit"),
         }
     }
 }
 
+/// Writer receiving a copy of what a pipeline stage sends to the next one.
+pub(crate) struct TeeWriter(pub(crate) Box<dyn Write + Send>);
+
+impl std::fmt::Debug for TeeWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("TeeWriter").field(&"<writer>").finish()
+    }
+}
+
+/// Callback run with the process id of a command once it is spawned.
+pub(crate) type OnSpawn = Arc<dyn Fn(u32) + Send + Sync>;
+
+/// Callback run with the status and duration of a command once it has exited.
+pub(crate) type OnExit = Arc<dyn Fn(&Status, Duration) + Send + Sync>;
+
+/// Callbacks observing a command, see [`Cmd::on_spawn`] and [`Cmd::on_exit`].
+#[derive(Clone, Default)]
+pub(crate) struct Observers {
+    pub(crate) on_spawn: Option<OnSpawn>,
+    pub(crate) on_exit: Option<OnExit>,
+}
+
+impl std::fmt::Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Observers")
+            .field("on_spawn", &self.on_spawn.is_some())
+            .field("on_exit", &self.on_exit.is_some())
+            .finish()
+    }
+}
+
+/// The exit callback of a spawned command and when it was spawned.
+pub(crate) struct ExitWatch {
+    pub(crate) started: Instant,
+    pub(crate) on_exit: Option<OnExit>,
+}
+
 /// A simple command builder.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Cmd {
     pub(crate) program: OsString,
     pub(crate) args: Vec<OsString>,
     pub(crate) envs: Vec<(OsString, OsString)>,
+    pub(crate) env_removes: Vec<OsString>,
+    pub(crate) env_clear: bool,
+    pub(crate) env_keeps: Vec<OsString>,
     pub(crate) current_dir: Option<PathBuf>,
+    pub(crate) check_current_dir: bool,
     pub(crate) suppress_echo: bool,
+    pub(crate) allow_failure: bool,
+    pub(crate) inherit: bool,
+    pub(crate) stdout_redirect: Option<Redirect>,
+    pub(crate) stderr_redirect: Option<Redirect>,
+    pub(crate) label: Option<String>,
+    pub(crate) install_hint: Option<String>,
+    pub(crate) expand_arg_files: bool,
+    pub(crate) arg0: Option<OsString>,
+    pub(crate) new_process_group: bool,
+    pub(crate) uid: Option<u32>,
+    pub(crate) gid: Option<u32>,
+    pub(crate) kill_on_drop: bool,
+    pub(crate) observers: Observers,
+}
+
+/// Destination an output stream of a command is redirected to.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Redirect {
+    /// Write to a file, truncating it or appending to it.
+    File { path: PathBuf, append: bool },
+    /// Discard the output.
+    Null,
 }
 
 /// Specifies which output streams should be piped between commands.
 ///
-/// This enum is used internally to track pipe modes, but you typically don't need
-/// to use it directly. Instead, use the convenient builder methods on `Cmd`:
+/// To connect commands you typically don't need to use it directly. Instead, use
+/// the convenient builder methods on `Cmd`:
 ///
 /// - `pipe(cmd)` - pipes stdout (default)
 /// - `pipe_stderr(cmd)` - pipes stderr only
 /// - `pipe_both(cmd)` - pipes both stdout and stderr combined
 ///
+/// It is also used with `output_bytes_of` to select which stream of the final
+/// command is captured.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -72,7 +152,7 @@ pub struct Cmd {
 /// # Ok::<(), Box<dyn std::error::Error>>(())
 /// ```
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub(crate) enum PipeMode {
+pub enum PipeMode {
     /// Pipe only stdout between commands (default behavior).
     ///
     /// This is the standard Unix pipe behavior where each command's stdout
@@ -95,6 +175,50 @@ pub(crate) enum PipeMode {
 /// Handle to a spawned pipeline for waiting and collecting results.
 pub struct PipelineHandle {
     pub(crate) children: Vec<Child>,
+    /// Exit callbacks, one per child.
+    pub(crate) watches: Vec<ExitWatch>,
+    pub(crate) tees: Vec<JoinHandle<()>>,
+    /// The pipeline as echoed, to name it in errors.
+    pub(crate) rendered: String,
+}
+
+/// Captured result of a finished command or pipeline.
+#[derive(Debug, Clone)]
+pub struct CmdOutput {
+    /// Exit status of the command.
+    pub status: ExitStatus,
+    /// Everything the command wrote to stdout.
+    pub stdout: Vec<u8>,
+    /// Everything the command wrote to stderr.
+    pub stderr: Vec<u8>,
+}
+
+/// A command captured by [`record`](crate::record) instead of being executed.
+#[derive(Debug, Clone)]
+pub struct RecordedCommand {
+    /// The command line as it would have been echoed.
+    pub rendered: String,
+    /// Snapshot of each command in the pipeline, in order.
+    pub commands: Vec<Cmd>,
+}
+
+/// Handle to a single spawned command whose output is consumed in the background.
+pub struct ChildHandle {
+    pub(crate) child: Option<Child>,
+    pub(crate) reader: Option<JoinHandle<std::io::Result<()>>>,
+    pub(crate) kill_on_drop: bool,
+    pub(crate) allow_failure: bool,
+    pub(crate) watch: Option<ExitWatch>,
+    /// The command as echoed, to name it in errors.
+    pub(crate) rendered: String,
+}
+
+/// Handle to a single command running in the background, created by
+/// [`Cmd::spawn`].
+pub struct CmdHandle {
+    pub(crate) child: Option<Child>,
+    pub(crate) kill_on_drop: bool,
+    pub(crate) watch: Option<ExitWatch>,
 }
 
 /// Complete I/O access to a spawned pipeline.
@@ -105,10 +229,34 @@ pub struct PipelineSpawn {
     pub stderr: Option<std::process::ChildStderr>,
 }
 
+/// Output of a running pipeline, created by [`Pipeline::reader`].
+///
+/// Reads return the output of the last command as it arrives. Call
+/// [`wait`](Self::wait) once done to check how the pipeline exited; a reader
+/// dropped without waiting kills the commands that are still running.
+pub struct PipelineReader {
+    pub(crate) stdout: Option<std::process::ChildStdout>,
+    /// Taken by `wait`; whatever is left when dropped is killed.
+    pub(crate) handle: Option<PipelineHandle>,
+    pub(crate) input: Option<JoinHandle<()>>,
+    pub(crate) stderr_reader: Option<JoinHandle<std::io::Result<()>>>,
+    pub(crate) stderr: Arc<std::sync::Mutex<Vec<u8>>>,
+    pub(crate) allow_failure: bool,
+    pub(crate) pipefail: bool,
+}
+
 /// A pipeline of commands.
 #[derive(Debug)]
 pub struct Pipeline {
     pub(crate) connections: Vec<(Cmd, PipeMode)>,
     pub(crate) input: Option<CmdInput>,
     pub(crate) suppress_echo: bool,
+    pub(crate) allow_failure: bool,
+    pub(crate) pipefail: bool,
+    pub(crate) tail_lines: Option<usize>,
+    /// Writers copying the output of the stage at the given index.
+    pub(crate) tees: Vec<(usize, TeeWriter)>,
+    pub(crate) buffer_size: usize,
+    /// Working directory of the stages without one of their own.
+    pub(crate) current_dir: Option<PathBuf>,
 }
@@ -0,0 +1,60 @@
+//! Polling helpers for readiness checks.
+
+use crate::cmd::{error::Error, types::Cmd};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Repeatedly run a command until its stdout contains `needle`.
+///
+/// `make` is called to build a fresh command for every attempt, since a `Cmd`
+/// is consumed when it runs. Failed attempts (non-zero exit, spawn errors) are
+/// treated as "not ready yet" and retried after `interval`. Returns the stdout
+/// of the first matching attempt, or an error once `timeout` has elapsed.
+///
+/// Every attempt is echoed like a normal command; use `.no_echo()` inside
+/// `make` to keep the trace quiet while polling.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+/// use std::time::Duration;
+///
+/// wait_for(
+///     || cmd!("pg_isready").no_echo(),
+///     "accepting connections",
+///     Duration::from_secs(30),
+///     Duration::from_millis(500),
+/// )?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn wait_for(
+    make: impl Fn() -> Cmd,
+    needle: &str,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<String, Error> {
+    let start = Instant::now();
+
+    loop {
+        let last_error = match make().output() {
+            Ok(output) if output.contains(needle) => return Ok(output),
+            Ok(_) => None,
+            Err(e) => Some(e),
+        };
+
+        let elapsed = start.elapsed();
+        if elapsed >= timeout {
+            let mut message = format!(
+                "Timed out after {:?} waiting for output containing {:?}",
+                timeout, needle
+            );
+            if let Some(e) = last_error {
+                message.push_str(&format!(" (last error: {})", e));
+            }
+            return Err(Error::timeout(message));
+        }
+
+        thread::sleep(interval.min(timeout - elapsed));
+    }
+}
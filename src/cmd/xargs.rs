@@ -0,0 +1,104 @@
+//! Passing the output of a pipeline as arguments to a command, like `xargs`.
+
+use crate::cmd::{
+    error::Error,
+    types::{Cmd, Pipeline},
+};
+use std::ffi::OsString;
+
+/// A command run with the output of a pipeline as its arguments, created by
+/// [`Pipeline::xargs`].
+///
+/// The output is split on whitespace, or on null bytes after
+/// [`null`](Self::null), and each item is appended to the command as one
+/// argument. Quotes and backslashes have no special meaning. The command is
+/// not run at all when there are no items.
+#[derive(Debug)]
+pub struct Xargs {
+    source: Pipeline,
+    cmd: Cmd,
+    null: bool,
+    batch: Option<usize>,
+}
+
+impl Xargs {
+    pub(crate) fn new(source: Pipeline, cmd: Cmd) -> Self {
+        Self {
+            source,
+            cmd,
+            null: false,
+            batch: None,
+        }
+    }
+
+    /// Split the output on null bytes instead of whitespace, like `xargs -0`.
+    ///
+    /// Use this with `find -print0` and similar for names containing spaces
+    /// or newlines.
+    pub fn null(mut self) -> Self {
+        self.null = true;
+        self
+    }
+
+    /// Run the command once for every `size` items instead of once for all
+    /// of them, like `xargs -n`. A size of 0 is treated as 1.
+    pub fn batch(mut self, size: usize) -> Self {
+        self.batch = Some(size.max(1));
+        self
+    }
+
+    /// Run the command for the items, stopping at the first failure.
+    pub fn run(self) -> Result<(), Error> {
+        for cmd in self.commands()? {
+            cmd.run()?;
+        }
+        Ok(())
+    }
+
+    /// Run the command for the items and return the combined stdout of all runs.
+    pub fn output(self) -> Result<String, Error> {
+        let mut output = String::new();
+        for cmd in self.commands()? {
+            output.push_str(&cmd.output()?);
+        }
+        Ok(output)
+    }
+
+    /// Run the source pipeline and build one command per batch of items.
+    fn commands(self) -> Result<Vec<Cmd>, Error> {
+        let bytes = self.source.output_bytes()?;
+        let items: Vec<OsString> = if self.null {
+            bytes
+                .split(|&b| b == 0)
+                .filter(|item| !item.is_empty())
+                .map(os_string)
+                .collect()
+        } else {
+            bytes
+                .split(|b| b.is_ascii_whitespace())
+                .filter(|item| !item.is_empty())
+                .map(os_string)
+                .collect()
+        };
+        if items.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let batch = self.batch.unwrap_or(items.len());
+        Ok(items
+            .chunks(batch)
+            .map(|chunk| self.cmd.clone().args(chunk))
+            .collect())
+    }
+}
+
+#[cfg(unix)]
+fn os_string(bytes: &[u8]) -> OsString {
+    use std::os::unix::ffi::OsStrExt;
+    std::ffi::OsStr::from_bytes(bytes).to_os_string()
+}
+
+#[cfg(not(unix))]
+fn os_string(bytes: &[u8]) -> OsString {
+    String::from_utf8_lossy(bytes).into_owned().into()
+}
@@ -1,4 +1,8 @@
-use anstyle::{AnsiColor, Color};
+use crate::style;
+use anstyle::{Ansi256Color, AnsiColor, Color, Effects, RgbColor, Style};
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU8, Ordering};
 
 // Basic colors
 pub const BLACK: Option<Color> = Some(Color::Ansi(AnsiColor::Black));
@@ -19,3 +23,921 @@ pub const BRIGHT_BLUE: Option<Color> = Some(Color::Ansi(AnsiColor::BrightBlue));
 pub const BRIGHT_MAGENTA: Option<Color> = Some(Color::Ansi(AnsiColor::BrightMagenta));
 pub const BRIGHT_CYAN: Option<Color> = Some(Color::Ansi(AnsiColor::BrightCyan));
 pub const BRIGHT_WHITE: Option<Color> = Some(Color::Ansi(AnsiColor::BrightWhite));
+
+/// Number of colors the terminal can display.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    /// 24-bit RGB colors are rendered as-is.
+    TrueColor,
+    /// RGB colors are downgraded to the nearest of the 256 indexed colors.
+    Ansi256,
+}
+
+// 0 = detect from the environment, otherwise an explicit `ColorDepth`
+static COLOR_DEPTH: AtomicU8 = AtomicU8::new(0);
+
+/// Returns `true` if the terminal advertises 24-bit color support.
+///
+/// This follows the common `COLORTERM=truecolor` / `COLORTERM=24bit` convention.
+pub fn supports_truecolor() -> bool {
+    std::env::var("COLORTERM").is_ok_and(|value| {
+        value.eq_ignore_ascii_case("truecolor") || value.eq_ignore_ascii_case("24bit")
+    })
+}
+
+/// Set the color depth explicitly instead of detecting it from `COLORTERM`.
+pub fn set_color_depth(depth: ColorDepth) {
+    let value = match depth {
+        ColorDepth::TrueColor => 1,
+        ColorDepth::Ansi256 => 2,
+    };
+    COLOR_DEPTH.store(value, Ordering::Relaxed);
+}
+
+/// The color depth in effect: the explicit setting, or the detected one.
+pub fn color_depth() -> ColorDepth {
+    match COLOR_DEPTH.load(Ordering::Relaxed) {
+        1 => ColorDepth::TrueColor,
+        2 => ColorDepth::Ansi256,
+        _ if supports_truecolor() => ColorDepth::TrueColor,
+        _ => ColorDepth::Ansi256,
+    }
+}
+
+/// Adapt a color to the current color depth, downgrading RGB when needed.
+pub fn adapt(color: Color) -> Color {
+    match (color, color_depth()) {
+        (Color::Rgb(rgb), ColorDepth::Ansi256) => Color::Ansi256(rgb_to_ansi256(rgb)),
+        (color, _) => color,
+    }
+}
+
+/// Adapt the foreground and background colors of a style to the current color depth.
+pub fn adapt_style(style: Style) -> Style {
+    style
+        .fg_color(style.get_fg_color().map(adapt))
+        .bg_color(style.get_bg_color().map(adapt))
+}
+
+// 0 = detect from `NO_COLOR` and the terminal, 1 = enabled, 2 = disabled
+static ENABLED: AtomicU8 = AtomicU8::new(0);
+
+/// Enable or disable colors in echoed output, overriding detection.
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(if enabled { 1 } else { 2 }, Ordering::Relaxed);
+}
+
+/// Returns `true` if echoed output is colored.
+///
+/// Unless set with [`set_enabled`], colors are used when stderr is a terminal and
+/// the `NO_COLOR` environment variable is not set to a non-empty value (see
+/// <https://no-color.org>), so that redirected logs contain plain text.
+pub fn enabled() -> bool {
+    match ENABLED.load(Ordering::Relaxed) {
+        1 => true,
+        2 => false,
+        _ => {
+            use std::io::IsTerminal;
+            std::env::var_os("NO_COLOR").is_none_or(|v| v.is_empty())
+                && std::io::stderr().is_terminal()
+        }
+    }
+}
+
+/// The style to echo with: adapted to the color depth, or plain if colors are
+/// disabled.
+pub(crate) fn echo_style(style: Style) -> Style {
+    if enabled() {
+        adapt_style(style)
+    } else {
+        Style::new()
+    }
+}
+
+/// A value displayed with a style, created with the [`StyleExt`] methods.
+///
+/// The style is applied when the value is formatted, and only if colors are
+/// [`enabled`], so styled text turns plain under `NO_COLOR` like the echoed
+/// commands do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Styled<T> {
+    value: T,
+    style: Style,
+}
+
+/// Builders to style any displayable value, e.g. for use with `echo!`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// echo!("Status:", "OK".green().bold());
+/// echo!("Retries:", 3.yellow());
+/// echo!("Theme:", "dodger blue".hex("#1e90ff")?.bold());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait StyleExt: fmt::Display + Sized {
+    /// Display the value with `style`.
+    fn style(self, style: Style) -> Styled<Self> {
+        Styled { value: self, style }
+    }
+
+    /// Display the value in the foreground color `color`.
+    fn fg(self, color: Color) -> Styled<Self> {
+        self.style(Style::new()).fg(color)
+    }
+
+    /// Display the value on the background color `color`.
+    fn on(self, color: Color) -> Styled<Self> {
+        self.style(Style::new()).on(color)
+    }
+
+    /// Display the value in the color given as a hex code, see [`parse_hex`].
+    fn hex(self, hex: &str) -> Result<Styled<Self>, ParseColorError> {
+        self.style(Style::new()).hex(hex)
+    }
+
+    /// Display the value on the background color given as a hex code, see
+    /// [`parse_hex`].
+    fn bg_hex(self, hex: &str) -> Result<Styled<Self>, ParseColorError> {
+        self.style(Style::new()).bg_hex(hex)
+    }
+
+    /// Display the value in red.
+    fn red(self) -> Styled<Self> {
+        self.style(Style::new()).red()
+    }
+
+    /// Display the value in green.
+    fn green(self) -> Styled<Self> {
+        self.style(Style::new()).green()
+    }
+
+    /// Display the value in yellow.
+    fn yellow(self) -> Styled<Self> {
+        self.style(Style::new()).yellow()
+    }
+
+    /// Display the value in blue.
+    fn blue(self) -> Styled<Self> {
+        self.style(Style::new()).blue()
+    }
+
+    /// Display the value in magenta.
+    fn magenta(self) -> Styled<Self> {
+        self.style(Style::new()).magenta()
+    }
+
+    /// Display the value in cyan.
+    fn cyan(self) -> Styled<Self> {
+        self.style(Style::new()).cyan()
+    }
+
+    /// Display the value in gray.
+    fn gray(self) -> Styled<Self> {
+        self.style(Style::new()).gray()
+    }
+
+    /// Display the value in bold.
+    fn bold(self) -> Styled<Self> {
+        self.style(Style::new()).bold()
+    }
+
+    /// Display the value dimmed.
+    fn dim(self) -> Styled<Self> {
+        self.style(Style::new()).dim()
+    }
+
+    /// Display the value in italics.
+    fn italic(self) -> Styled<Self> {
+        self.style(Style::new()).italic()
+    }
+
+    /// Display the value underlined.
+    fn underline(self) -> Styled<Self> {
+        self.style(Style::new()).underline()
+    }
+}
+
+impl<T: fmt::Display> StyleExt for T {}
+
+impl<T> Styled<T> {
+    /// The style the value is displayed with.
+    pub fn get_style(&self) -> Style {
+        self.style
+    }
+
+    /// Replace the style.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Set the foreground color.
+    pub fn fg(mut self, color: Color) -> Self {
+        self.style = self.style.fg_color(Some(color));
+        self
+    }
+
+    /// Set the background color.
+    pub fn on(mut self, color: Color) -> Self {
+        self.style = self.style.bg_color(Some(color));
+        self
+    }
+
+    /// Set the foreground color from a hex code, see [`parse_hex`].
+    pub fn hex(self, hex: &str) -> Result<Self, ParseColorError> {
+        Ok(self.fg(parse_hex(hex)?))
+    }
+
+    /// Set the background color from a hex code, see [`parse_hex`].
+    pub fn bg_hex(self, hex: &str) -> Result<Self, ParseColorError> {
+        Ok(self.on(parse_hex(hex)?))
+    }
+
+    fn effect(mut self, effect: Effects) -> Self {
+        self.style = self.style.effects(self.style.get_effects() | effect);
+        self
+    }
+
+    /// Use red as the foreground color.
+    pub fn red(self) -> Self {
+        self.fg(Color::Ansi(AnsiColor::Red))
+    }
+
+    /// Use green as the foreground color.
+    pub fn green(self) -> Self {
+        self.fg(Color::Ansi(AnsiColor::Green))
+    }
+
+    /// Use yellow as the foreground color.
+    pub fn yellow(self) -> Self {
+        self.fg(Color::Ansi(AnsiColor::Yellow))
+    }
+
+    /// Use blue as the foreground color.
+    pub fn blue(self) -> Self {
+        self.fg(Color::Ansi(AnsiColor::Blue))
+    }
+
+    /// Use magenta as the foreground color.
+    pub fn magenta(self) -> Self {
+        self.fg(Color::Ansi(AnsiColor::Magenta))
+    }
+
+    /// Use cyan as the foreground color.
+    pub fn cyan(self) -> Self {
+        self.fg(Color::Ansi(AnsiColor::Cyan))
+    }
+
+    /// Use gray as the foreground color.
+    pub fn gray(self) -> Self {
+        self.fg(Color::Ansi(AnsiColor::BrightBlack))
+    }
+
+    /// Add bold.
+    pub fn bold(self) -> Self {
+        self.effect(Effects::BOLD)
+    }
+
+    /// Add dimming.
+    pub fn dim(self) -> Self {
+        self.effect(Effects::DIMMED)
+    }
+
+    /// Add italics.
+    pub fn italic(self) -> Self {
+        self.effect(Effects::ITALIC)
+    }
+
+    /// Add underlining.
+    pub fn underline(self) -> Self {
+        self.effect(Effects::UNDERLINE)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Styled<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let style = echo_style(self.style);
+        write!(f, "{style}")?;
+        self.value.fmt(f)?;
+        write!(f, "{style:#}")
+    }
+}
+
+/// Remove ANSI escape sequences from `s`, leaving the plain text.
+///
+/// This strips CSI sequences, i.e. colors and other styles with any number of
+/// parameters as well as cursor movement and erase codes, OSC sequences such
+/// as terminal titles and hyperlinks, and other two-character escapes. Useful
+/// to parse the output of commands that color it, like `ls --color=always`.
+///
+/// # Examples
+///
+/// ```
+/// use scriptify::color::strip;
+///
+/// assert_eq!(strip("\x1b[1;32mok\x1b[0m done\x1b[K"), "ok done");
+/// ```
+pub fn strip(s: &str) -> String {
+    let mut plain = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            plain.push(c);
+            continue;
+        }
+        match chars.next() {
+            // CSI: parameter and intermediate bytes up to a final byte
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // OSC: up to BEL or the string terminator `ESC \`
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            // Any other escape is a single character
+            _ => {}
+        }
+    }
+    plain
+}
+
+/// Find the nearest color in the 256-color palette (6x6x6 cube or grayscale ramp).
+fn rgb_to_ansi256(RgbColor(r, g, b): RgbColor) -> Ansi256Color {
+    if r == g && g == b {
+        let index = match r {
+            0..8 => 16,
+            249.. => 231,
+            _ => 232 + ((u16::from(r) - 8) * 24 + 123) / 247,
+        };
+        return Ansi256Color(index as u8);
+    }
+
+    let level = |v: u8| (u16::from(v) * 5 + 127) / 255;
+    Ansi256Color((16 + 36 * level(r) + 6 * level(g) + level(b)) as u8)
+}
+
+/// Parse a color from a name, a hex code or a 256-color palette index.
+///
+/// Accepted forms are the sixteen ANSI names (`"red"`, `"bright_red"`, with `-`
+/// allowed in place of `_`), `"gray"` / `"grey"`, the RGB colors `"orange"`,
+/// `"purple"`, `"pink"` and `"brown"`, `"#rrggbb"` / `"#rgb"` for RGB colors,
+/// and `"0"` to `"255"` for the 256-color palette. Matching is case-insensitive; `None`
+/// is returned for anything else.
+///
+/// # Examples
+///
+/// ```
+/// use scriptify::color::parse_color;
+///
+/// assert_eq!(parse_color("bright_red"), scriptify::color::BRIGHT_RED);
+/// assert!(parse_color("#ff8800").is_some());
+/// assert_eq!(parse_color("chartreuse-ish"), None);
+/// ```
+pub fn parse_color(s: &str) -> Option<Color> {
+    let s = s.trim().to_ascii_lowercase().replace('-', "_");
+
+    if let Some(hex) = s.strip_prefix('#') {
+        return parse_hex_digits(hex);
+    }
+
+    if let Ok(index) = s.parse::<u8>() {
+        return Some(Color::Ansi256(Ansi256Color(index)));
+    }
+
+    let ansi = match s.as_str() {
+        "black" => AnsiColor::Black,
+        "red" => AnsiColor::Red,
+        "green" => AnsiColor::Green,
+        "yellow" => AnsiColor::Yellow,
+        "blue" => AnsiColor::Blue,
+        "magenta" => AnsiColor::Magenta,
+        "cyan" => AnsiColor::Cyan,
+        "white" => AnsiColor::White,
+        "bright_black" => AnsiColor::BrightBlack,
+        "bright_red" => AnsiColor::BrightRed,
+        "bright_green" => AnsiColor::BrightGreen,
+        "bright_yellow" => AnsiColor::BrightYellow,
+        "bright_blue" => AnsiColor::BrightBlue,
+        "bright_magenta" => AnsiColor::BrightMagenta,
+        "bright_cyan" => AnsiColor::BrightCyan,
+        "bright_white" => AnsiColor::BrightWhite,
+        "gray" | "grey" => AnsiColor::BrightBlack,
+        "orange" => return Some(Color::Rgb(RgbColor(255, 165, 0))),
+        "purple" => return Some(Color::Rgb(RgbColor(128, 0, 128))),
+        "pink" => return Some(Color::Rgb(RgbColor(255, 192, 203))),
+        "brown" => return Some(Color::Rgb(RgbColor(165, 42, 42))),
+        _ => return None,
+    };
+    Some(Color::Ansi(ansi))
+}
+
+/// Parse the digits of a `rrggbb` or `rgb` hex color.
+fn parse_hex_digits(hex: &str) -> Option<Color> {
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    if !hex.is_ascii() {
+        return None;
+    }
+    match hex.len() {
+        6 => Some(Color::Rgb(RgbColor(
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        ))),
+        3 => {
+            let short = |i: usize| channel(&hex[i..=i]).map(|v| v * 17);
+            Some(Color::Rgb(RgbColor(short(0)?, short(1)?, short(2)?)))
+        }
+        _ => None,
+    }
+}
+
+/// Parse a hex color such as `"#1e90ff"` or `"#f80"`, with or without the `#`.
+///
+/// # Examples
+///
+/// ```
+/// use anstyle::{Color, RgbColor};
+/// use scriptify::color::parse_hex;
+///
+/// assert_eq!(parse_hex("#1e90ff"), Ok(Color::Rgb(RgbColor(30, 144, 255))));
+/// assert!(parse_hex("#1e90f").is_err());
+/// ```
+pub fn parse_hex(hex: &str) -> Result<Color, ParseColorError> {
+    let digits = hex.trim();
+    let digits = digits.strip_prefix('#').unwrap_or(digits);
+    parse_hex_digits(digits).ok_or_else(|| ParseColorError {
+        input: hex.to_string(),
+        expected: "a hex color like #rrggbb or #rgb",
+    })
+}
+
+/// Error returned when a color cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError {
+    input: String,
+    expected: &'static str,
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid color {:?}: expected {}",
+            self.input, self.expected
+        )
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+/// Parse a style from comma-separated modifiers and colors.
+///
+/// Each item is either an effect (`bold`, `dim`, `italic`, `underline`, `blink`,
+/// `reverse`, `hidden`, `strikethrough`), a foreground color in any form accepted
+/// by [`parse_color`], or a background color prefixed with `on_` (e.g.
+/// `"on_blue"`, `"on_#202020"`). Returns `None` if any item is not recognized.
+///
+/// # Examples
+///
+/// ```
+/// use scriptify::color::parse_style;
+///
+/// let style = parse_style("bold,underline,#ff8800").unwrap();
+/// assert!(style.get_effects().contains(anstyle::Effects::BOLD));
+/// assert_eq!(parse_style("bold,sparkly"), None);
+/// ```
+pub fn parse_style(s: &str) -> Option<Style> {
+    let mut style = Style::new();
+    for item in s.split(',').map(str::trim).filter(|item| !item.is_empty()) {
+        let effect = match item.to_ascii_lowercase().as_str() {
+            "bold" => Some(Effects::BOLD),
+            "dim" => Some(Effects::DIMMED),
+            "italic" => Some(Effects::ITALIC),
+            "underline" => Some(Effects::UNDERLINE),
+            "blink" => Some(Effects::BLINK),
+            "reverse" => Some(Effects::INVERT),
+            "hidden" => Some(Effects::HIDDEN),
+            "strikethrough" => Some(Effects::STRIKETHROUGH),
+            _ => None,
+        };
+        style = match effect {
+            Some(effect) => style.effects(style.get_effects() | effect),
+            None => match item.strip_prefix("on_") {
+                Some(background) => style.bg_color(Some(parse_color(background)?)),
+                None => style.fg_color(Some(parse_color(item)?)),
+            },
+        };
+    }
+    Some(style)
+}
+
+/// Styles used to render the command trace.
+///
+/// Each field styles one kind of element of an echoed `cmd` or `fs` line. Use
+/// one of the presets, or select a preset globally with [`set_theme_preset`] or
+/// the `SCRIPTIFY_THEME` environment variable. A theme of your own is set with
+/// [`set_theme`].
+///
+/// # Examples
+///
+/// ```
+/// use anstyle::Style;
+/// use scriptify::color::{self, Theme};
+///
+/// color::set_theme(Theme {
+///     program: Style::new().fg_color(color::GREEN).bold(),
+///     ..Theme::dark()
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+    /// The `cmd` / `fs` prefix of each line.
+    pub prefix: Style,
+    /// Program names and file system operations.
+    pub program: Style,
+    /// Arguments and file system operation details.
+    pub arg: Style,
+    /// Pipe and redirection operators.
+    pub operator: Style,
+    /// The `cd:` and `env:` markers.
+    pub keyword: Style,
+    /// Working directories, environment assignments and redirection targets.
+    pub value: Style,
+}
+
+impl Theme {
+    /// The default theme, designed for dark terminal backgrounds.
+    pub const fn dark() -> Self {
+        Self {
+            prefix: style::BRIGHT_BLACK,
+            program: style::BOLD_CYAN,
+            arg: style::BOLD_UNDERLINE,
+            operator: style::MAGENTA,
+            keyword: style::BRIGHT_BLUE,
+            value: style::UNDERLINE_BRIGHT_BLUE,
+        }
+    }
+
+    /// A theme using darker colors that stay legible on light backgrounds.
+    pub const fn light() -> Self {
+        Self {
+            prefix: Style::new().fg_color(BRIGHT_BLACK),
+            program: Style::new().fg_color(BLUE).bold(),
+            arg: Style::new().bold().underline(),
+            operator: Style::new().fg_color(MAGENTA),
+            keyword: Style::new().fg_color(BLUE),
+            value: Style::new().fg_color(BLUE).underline(),
+        }
+    }
+
+    /// The default colors without bold or underline.
+    pub const fn minimal() -> Self {
+        Self {
+            prefix: Style::new().fg_color(BRIGHT_BLACK),
+            program: Style::new().fg_color(CYAN),
+            arg: Style::new(),
+            operator: Style::new().fg_color(MAGENTA),
+            keyword: Style::new().fg_color(BRIGHT_BLUE),
+            value: Style::new().fg_color(BRIGHT_BLUE),
+        }
+    }
+
+    /// No styling at all.
+    pub const fn plain() -> Self {
+        Self {
+            prefix: Style::new(),
+            program: Style::new(),
+            arg: Style::new(),
+            operator: Style::new(),
+            keyword: Style::new(),
+            value: Style::new(),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+/// The built-in themes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemePreset {
+    /// [`Theme::dark`], the default.
+    Dark,
+    /// [`Theme::light`].
+    Light,
+    /// [`Theme::minimal`].
+    Minimal,
+    /// [`Theme::plain`].
+    Plain,
+}
+
+impl ThemePreset {
+    /// Look up a preset by its lowercase name (`dark`, `light`, `minimal`, `plain`).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "dark" => Some(Self::Dark),
+            "light" => Some(Self::Light),
+            "minimal" => Some(Self::Minimal),
+            "plain" => Some(Self::Plain),
+            _ => None,
+        }
+    }
+
+    /// The theme for this preset.
+    pub const fn theme(self) -> Theme {
+        match self {
+            Self::Dark => Theme::dark(),
+            Self::Light => Theme::light(),
+            Self::Minimal => Theme::minimal(),
+            Self::Plain => Theme::plain(),
+        }
+    }
+}
+
+// 0 = read `SCRIPTIFY_THEME`, otherwise an explicit `ThemePreset`
+static THEME_PRESET: AtomicU8 = AtomicU8::new(0);
+
+// A theme set with `set_theme`, taking precedence over the preset
+static CUSTOM_THEME: Mutex<Option<Theme>> = Mutex::new(None);
+
+/// Select the theme preset explicitly instead of reading `SCRIPTIFY_THEME`.
+///
+/// This replaces a theme set with [`set_theme`].
+pub fn set_theme_preset(preset: ThemePreset) {
+    let value = match preset {
+        ThemePreset::Dark => 1,
+        ThemePreset::Light => 2,
+        ThemePreset::Minimal => 3,
+        ThemePreset::Plain => 4,
+    };
+    *CUSTOM_THEME.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    THEME_PRESET.store(value, Ordering::Relaxed);
+}
+
+/// Use `theme` to render the command trace.
+///
+/// This takes precedence over `SCRIPTIFY_THEME` and replaces a preset selected
+/// with [`set_theme_preset`].
+pub fn set_theme(theme: Theme) {
+    *CUSTOM_THEME.lock().unwrap_or_else(|e| e.into_inner()) = Some(theme);
+}
+
+/// The theme in effect: the one set with [`set_theme`], the explicit preset,
+/// the one named by `SCRIPTIFY_THEME`, or [`Theme::dark`].
+pub fn theme() -> Theme {
+    if let Some(theme) = *CUSTOM_THEME.lock().unwrap_or_else(|e| e.into_inner()) {
+        return theme;
+    }
+    let preset = match THEME_PRESET.load(Ordering::Relaxed) {
+        1 => ThemePreset::Dark,
+        2 => ThemePreset::Light,
+        3 => ThemePreset::Minimal,
+        4 => ThemePreset::Plain,
+        _ => std::env::var("SCRIPTIFY_THEME")
+            .ok()
+            .and_then(|name| ThemePreset::from_name(&name))
+            .unwrap_or(ThemePreset::Dark),
+    };
+    preset.theme()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn test_rgb_to_ansi256() {
+        assert_eq!(rgb_to_ansi256(RgbColor(255, 0, 0)), Ansi256Color(196));
+        assert_eq!(rgb_to_ansi256(RgbColor(0, 0, 255)), Ansi256Color(21));
+        assert_eq!(rgb_to_ansi256(RgbColor(255, 136, 0)), Ansi256Color(214));
+        assert_eq!(rgb_to_ansi256(RgbColor(0, 0, 0)), Ansi256Color(16));
+        assert_eq!(rgb_to_ansi256(RgbColor(255, 255, 255)), Ansi256Color(231));
+        assert_eq!(rgb_to_ansi256(RgbColor(128, 128, 128)), Ansi256Color(244));
+    }
+
+    #[test]
+    #[serial]
+    fn test_adapt_follows_color_depth() {
+        let orange = Color::Rgb(RgbColor(255, 136, 0));
+        let red = Color::Ansi(AnsiColor::Red);
+
+        set_color_depth(ColorDepth::TrueColor);
+        assert_eq!(color_depth(), ColorDepth::TrueColor);
+        assert_eq!(adapt(orange), orange);
+
+        set_color_depth(ColorDepth::Ansi256);
+        assert_eq!(adapt(orange), Color::Ansi256(Ansi256Color(214)));
+        assert_eq!(adapt(red), red);
+
+        let style = adapt_style(Style::new().fg_color(Some(orange)).bold());
+        assert_eq!(
+            style.get_fg_color(),
+            Some(Color::Ansi256(Ansi256Color(214)))
+        );
+        assert!(style.get_effects().contains(anstyle::Effects::BOLD));
+
+        // Restore detection from the environment
+        COLOR_DEPTH.store(0, Ordering::Relaxed);
+    }
+    #[test]
+    #[serial]
+    fn test_theme_presets() {
+        assert_eq!(ThemePreset::from_name("Light"), Some(ThemePreset::Light));
+        assert_eq!(ThemePreset::from_name("neon"), None);
+        assert_eq!(Theme::default(), Theme::dark());
+
+        let minimal = Theme::minimal();
+        for style in [minimal.program, minimal.arg, minimal.value] {
+            assert!(style.get_effects().is_plain());
+        }
+        assert_eq!(Theme::plain().program, Style::new());
+
+        // SAFETY: serialized with the other tests touching global color state
+        unsafe { std::env::set_var("SCRIPTIFY_THEME", "plain") };
+        assert_eq!(theme(), Theme::plain());
+
+        // An explicit preset takes precedence over the environment
+        set_theme_preset(ThemePreset::Light);
+        assert_eq!(theme(), Theme::light());
+
+        THEME_PRESET.store(0, Ordering::Relaxed);
+        unsafe { std::env::remove_var("SCRIPTIFY_THEME") };
+        assert_eq!(theme(), Theme::dark());
+    }
+    #[test]
+    #[serial]
+    fn test_set_theme() {
+        let custom = Theme {
+            program: Style::new().fg_color(GREEN).underline(),
+            arg: Style::new().fg_color(YELLOW),
+            ..Theme::plain()
+        };
+        set_enabled(true);
+        set_theme(custom);
+        assert_eq!(theme(), custom);
+
+        let rendered = crate::cmd!("ls", "-l").into_pipeline().render(true);
+        let program = custom.program;
+        let arg = custom.arg;
+        assert_eq!(rendered, format!("{program}ls{program:#} {arg}-l{arg:#}"));
+
+        // Selecting a preset replaces the custom theme
+        set_theme_preset(ThemePreset::Light);
+        assert_eq!(theme(), Theme::light());
+
+        THEME_PRESET.store(0, Ordering::Relaxed);
+        ENABLED.store(0, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("red"), RED);
+        assert_eq!(parse_color(" Bright-Cyan "), BRIGHT_CYAN);
+        assert_eq!(
+            parse_color("#FF8800"),
+            Some(Color::Rgb(RgbColor(255, 136, 0)))
+        );
+        assert_eq!(parse_color("#f80"), Some(Color::Rgb(RgbColor(255, 136, 0))));
+        assert_eq!(parse_color("208"), Some(Color::Ansi256(Ansi256Color(208))));
+
+        assert_eq!(parse_color("Grey"), BRIGHT_BLACK);
+        assert_eq!(
+            parse_color("orange"),
+            Some(Color::Rgb(RgbColor(255, 165, 0)))
+        );
+
+        for invalid in ["", "violet", "#ff88", "#gg0000", "256", "#ffé"] {
+            assert_eq!(parse_color(invalid), None, "{invalid:?}");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn test_hex() {
+        let dodger_blue = Color::Rgb(RgbColor(30, 144, 255));
+        assert_eq!(parse_hex("#1e90ff"), Ok(dodger_blue));
+        assert_eq!(parse_hex("1E90FF"), Ok(dodger_blue));
+        assert_eq!(parse_hex("#f80"), Ok(Color::Rgb(RgbColor(255, 136, 0))));
+
+        for invalid in ["", "#", "#1e90f", "#1e90ffa", "#zz90ff", "red", "#ééé"] {
+            let err = parse_hex(invalid).unwrap_err();
+            assert!(
+                err.to_string().contains("expected a hex color"),
+                "{invalid:?}"
+            );
+        }
+        assert!("x".bg_hex("nope").is_err());
+
+        set_enabled(true);
+        set_color_depth(ColorDepth::TrueColor);
+        let styled = "x".hex("#1e90ff").unwrap().bg_hex("#000").unwrap();
+        assert_eq!(
+            styled.to_string(),
+            "x".fg(dodger_blue)
+                .on(Color::Rgb(RgbColor(0, 0, 0)))
+                .to_string()
+        );
+        assert!(styled.to_string().starts_with("\x1b[38;2;30;144;255m"));
+        ENABLED.store(0, Ordering::Relaxed);
+        COLOR_DEPTH.store(0, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn test_parse_style() {
+        let style = parse_style("bold, underline, #ff8800, on_blue").unwrap();
+        assert_eq!(style.get_effects(), Effects::BOLD | Effects::UNDERLINE);
+        assert_eq!(
+            style.get_fg_color(),
+            Some(Color::Rgb(RgbColor(255, 136, 0)))
+        );
+        assert_eq!(style.get_bg_color(), BLUE);
+
+        assert_eq!(parse_style(""), Some(Style::new()));
+        assert_eq!(parse_style("bold,sparkly"), None);
+        assert_eq!(parse_style("on_nothing"), None);
+    }
+
+    #[test]
+    #[serial]
+    fn test_styled() {
+        set_enabled(true);
+        let styled = "OK".green().bold();
+        assert_eq!(styled.get_style(), Style::new().fg_color(GREEN).bold());
+        assert_eq!(styled.to_string(), "\x1b[1m\x1b[32mOK\x1b[0m");
+        assert!(42.red().to_string().contains("42"));
+
+        set_enabled(false);
+        assert_eq!("OK".green().bold().to_string(), "OK");
+
+        ENABLED.store(0, Ordering::Relaxed);
+    }
+
+    #[test]
+    #[serial]
+    fn test_strip() {
+        set_enabled(true);
+        let styled = format!("{} {}", "OK".green().bold(), 42.red().underline());
+        assert!(styled.contains('\x1b'));
+        assert_eq!(strip(&styled), "OK 42");
+        ENABLED.store(0, Ordering::Relaxed);
+
+        assert_eq!(strip("\x1b[38;2;255;136;0mrgb\x1b[0m"), "rgb");
+        assert_eq!(strip("50%\x1b[2K\r\x1b[1A100%"), "50%\r100%");
+        assert_eq!(strip("\x1b]0;title\x07text"), "text");
+        assert_eq!(
+            strip("\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\"),
+            "link"
+        );
+        assert_eq!(strip("plain ünïcode"), "plain ünïcode");
+        assert_eq!(strip("cut off\x1b[1;3"), "cut off");
+    }
+
+    #[test]
+    #[serial]
+    fn test_enabled() {
+        let rendered = || crate::cmd!("echo", "hello").into_pipeline().render(true);
+        let original = std::env::var_os("NO_COLOR");
+
+        set_enabled(true);
+        assert!(enabled());
+        assert!(rendered().contains('\x1b'));
+
+        set_enabled(false);
+        assert!(!enabled());
+        assert_eq!(rendered(), "echo hello");
+
+        // NO_COLOR disables colors when nothing is set explicitly
+        ENABLED.store(0, Ordering::Relaxed);
+        unsafe {
+            std::env::set_var("NO_COLOR", "1");
+        }
+        assert!(!enabled());
+        assert!(!rendered().contains('\x1b'));
+
+        unsafe {
+            match original {
+                Some(val) => std::env::set_var("NO_COLOR", val),
+                None => std::env::remove_var("NO_COLOR"),
+            }
+        }
+    }
+}
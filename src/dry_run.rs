@@ -0,0 +1,43 @@
+//! Dry-run mode: echo commands and file operations without performing them.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// 0 = read `SCRIPTIFY_DRY_RUN`, 1 = enabled, 2 = disabled
+static DRY_RUN: AtomicU8 = AtomicU8::new(0);
+
+/// Enable or disable dry-run mode, overriding `SCRIPTIFY_DRY_RUN`.
+///
+/// In dry-run mode commands and pipelines are echoed but not spawned: `run()`
+/// succeeds and `output()` returns empty output. File operations in [`fs`](crate::fs)
+/// that modify the file system are echoed and skipped as well, while reading
+/// operations still run. This allows previewing what a destructive script would do.
+///
+/// Commands with echo disabled are skipped silently.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// set_dry_run(true);
+/// fs::remove_dir_all("build")?;
+/// cmd!("rm", "-rf", "/tmp/cache").run()?;
+/// set_dry_run(false);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(if enabled { 1 } else { 2 }, Ordering::Relaxed);
+}
+
+/// Returns `true` if dry-run mode is in effect.
+///
+/// This is the value set with [`set_dry_run`], or otherwise whether the
+/// `SCRIPTIFY_DRY_RUN` environment variable is set to a non-empty value other
+/// than `0`.
+pub fn is_dry_run() -> bool {
+    match DRY_RUN.load(Ordering::Relaxed) {
+        1 => true,
+        2 => false,
+        _ => std::env::var_os("SCRIPTIFY_DRY_RUN").is_some_and(|v| !v.is_empty() && v != "0"),
+    }
+}
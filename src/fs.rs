@@ -5,16 +5,29 @@
 //!
 //! For more information on the behavior of these functions, see the documentation for the corresponding
 //! functions in [`std::fs`].
+//!
+//! In dry-run mode (see [`set_dry_run`](crate::set_dry_run)), operations that modify the file system
+//! are echoed but not performed, and succeed. Copies and recursive removals still read the file
+//! system to echo how many bytes or entries they would affect.
 
+use crate::dry_run::is_dry_run;
 use crate::output::{conditional_eprintln, should_echo};
-use crate::style::{BOLD_CYAN, BOLD_UNDERLINE, BRIGHT_BLACK};
-use std::path::Path;
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    // Set while a `Quiet` method runs on this thread
+    static QUIET: Cell<bool> = const { Cell::new(false) };
+}
 
 fn echo_operation(op: &str, details: &str) {
-    if should_echo() {
-        let styled_fs = format!("{BRIGHT_BLACK}fs{BRIGHT_BLACK:#}");
-        let styled_op = format!("{BOLD_CYAN}{op}{BOLD_CYAN:#}");
-        let styled_details = format!("{BOLD_UNDERLINE}{details}{BOLD_UNDERLINE:#}");
+    if should_echo() && !QUIET.get() {
+        let theme = crate::color::theme();
+        let [prefix, program, arg] =
+            [theme.prefix, theme.program, theme.arg].map(crate::color::echo_style);
+        let styled_fs = format!("{prefix}fs{prefix:#}");
+        let styled_op = format!("{program}{op}{program:#}");
+        let styled_details = format!("{arg}{details}{arg:#}");
         conditional_eprintln(format_args!(
             "{} {} {}",
             styled_fs, styled_op, styled_details
@@ -22,22 +35,95 @@ fn echo_operation(op: &str, details: &str) {
     }
 }
 
+/// Prefix an I/O error message with the path(s) it relates to, keeping its kind.
+fn with_path_context(err: std::io::Error, path: &str) -> std::io::Error {
+    std::io::Error::new(err.kind(), format!("{path}: {err}"))
+}
+
+/// Append a slice to the end of a file, creating the file if it doesn't exist.
+///
+/// This opens the file with [`std::fs::OpenOptions`] in append mode and echoes the
+/// operation to the console.
+pub fn append(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    echo_operation(
+        "append",
+        &format!("{} bytes -> {}", contents.len(), path.display()),
+    );
+    if is_dry_run() {
+        return Ok(());
+    }
+    std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)?
+        .write_all(contents)
+}
+
 /// Copy the contents of one file to another.
 ///
 /// This is a wrapper around [`std::fs::copy`] that echoes the operation to the console.
+/// In dry-run mode the size of the source is echoed and returned instead.
 pub fn copy(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<u64> {
     let from = from.as_ref();
     let to = to.as_ref();
+    if is_dry_run() {
+        let bytes = dry_run_size(from);
+        echo_operation(
+            "copy",
+            &format!("{} -> {} ({bytes} bytes)", from.display(), to.display()),
+        );
+        return Ok(bytes);
+    }
     echo_operation("copy", &format!("{} -> {}", from.display(), to.display()));
     std::fs::copy(from, to)
 }
 
+/// Size of the file a dry-run copy would read, or 0 if it cannot be read.
+fn dry_run_size(path: &Path) -> u64 {
+    std::fs::metadata(path).map_or(0, |m| m.len())
+}
+
+/// Copy many files, echoing a single summary line instead of one line per file.
+///
+/// Each `(from, to)` pair is copied with [`std::fs::copy`]. Returns the number of files
+/// copied. On failure, the error names the pair that could not be copied and the
+/// remaining pairs are not processed.
+pub fn copy_files<I, P, Q>(pairs: I) -> std::io::Result<usize>
+where
+    I: IntoIterator<Item = (P, Q)>,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let mut count = 0;
+    let mut bytes = 0;
+    for (from, to) in pairs {
+        let (from, to) = (from.as_ref(), to.as_ref());
+        if is_dry_run() {
+            bytes += dry_run_size(from);
+        } else {
+            bytes += std::fs::copy(from, to).map_err(|e| {
+                with_path_context(e, &format!("{} -> {}", from.display(), to.display()))
+            })?;
+        }
+        count += 1;
+    }
+    echo_operation("copy_files", &format!("{count} files ({bytes} bytes)"));
+    Ok(count)
+}
+
 /// Create a new, empty directory at the provided path.
 ///
 /// This is a wrapper around [`std::fs::create_dir`] that echoes the operation to the console.
 pub fn create_dir(path: impl AsRef<Path>) -> std::io::Result<()> {
     let path = path.as_ref();
     echo_operation("create_dir", &path.display().to_string());
+    if is_dry_run() {
+        return Ok(());
+    }
     std::fs::create_dir(path)
 }
 
@@ -47,9 +133,123 @@ pub fn create_dir(path: impl AsRef<Path>) -> std::io::Result<()> {
 pub fn create_dir_all(path: impl AsRef<Path>) -> std::io::Result<()> {
     let path = path.as_ref();
     echo_operation("create_dir_all", &path.display().to_string());
+    if is_dry_run() {
+        return Ok(());
+    }
     std::fs::create_dir_all(path)
 }
 
+/// Find the paths matching a shell-style pattern, sorted.
+///
+/// Patterns are split on `/`. Within a component, `*` matches any sequence of
+/// characters and `?` matches a single character; a component that is exactly
+/// `**` matches any number of directories, including none. As in the shell,
+/// wildcards do not match names starting with `.` unless the pattern component
+/// does too, and symbolic links to directories are not followed by `**`.
+/// Relative patterns are resolved against the current directory and yield
+/// relative paths. The pattern is echoed to the console.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// for source in fs::glob("src/**/*.rs")? {
+///     cmd!("rustfmt", "--check", source).run()?;
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn glob(pattern: &str) -> std::io::Result<Vec<PathBuf>> {
+    echo_operation("glob", pattern);
+
+    let (root, rest) = match pattern.strip_prefix('/') {
+        Some(rest) => (PathBuf::from("/"), rest),
+        None => (PathBuf::new(), pattern),
+    };
+    let components: Vec<&str> = rest.split('/').filter(|c| !c.is_empty()).collect();
+
+    let mut matches = Vec::new();
+    glob_walk(&root, &components, &mut matches)?;
+    matches.sort();
+    matches.dedup();
+    Ok(matches)
+}
+
+fn glob_walk(dir: &Path, components: &[&str], matches: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    let Some((&component, rest)) = components.split_first() else {
+        if !dir.as_os_str().is_empty() {
+            matches.push(dir.to_path_buf());
+        }
+        return Ok(());
+    };
+
+    if !component.contains(['*', '?']) {
+        let path = dir.join(component);
+        if rest.is_empty() {
+            if std::fs::symlink_metadata(&path).is_ok() {
+                matches.push(path);
+            }
+        } else if path.is_dir() {
+            glob_walk(&path, rest, matches)?;
+        }
+        return Ok(());
+    }
+
+    let read_path = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let entries = match std::fs::read_dir(read_path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(with_path_context(e, &read_path.display().to_string())),
+    };
+
+    if component == "**" {
+        glob_walk(dir, rest, matches)?;
+    }
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        if name.starts_with('.') && !component.starts_with('.') {
+            continue;
+        }
+        let path = dir.join(name);
+        if component == "**" {
+            if entry.file_type()?.is_dir() {
+                glob_walk(&path, components, matches)?;
+            }
+        } else if wildcard_match(component.as_bytes(), name.as_bytes()) {
+            if rest.is_empty() {
+                matches.push(path);
+            } else if path.is_dir() {
+                glob_walk(&path, rest, matches)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Match a name against a pattern where `*` matches any sequence and `?` one character.
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| wildcard_match(rest, &name[i..])),
+        Some((b'?', rest)) => {
+            // Skip one whole UTF-8 character
+            let len = name
+                .first()
+                .map_or(0, |&b| (b.leading_ones() as usize).max(1));
+            !name.is_empty() && wildcard_match(rest, &name[len.min(name.len())..])
+        }
+        Some((c, rest)) => name.first() == Some(c) && wildcard_match(rest, &name[1..]),
+    }
+}
+
 /// Create a new hard link to a file.
 ///
 /// This is a wrapper around [`std::fs::hard_link`] that echoes the operation to the console.
@@ -60,9 +260,42 @@ pub fn hard_link(original: impl AsRef<Path>, link: impl AsRef<Path>) -> std::io:
         "hard_link",
         &format!("{} -> {}", original.display(), link.display()),
     );
+    if is_dry_run() {
+        return Ok(());
+    }
     std::fs::hard_link(original, link)
 }
 
+/// Returns `true` if the path exists and points to a regular file.
+///
+/// Like the shell's `[ -f path ]`, symbolic links are followed and a missing path
+/// yields `false` instead of an error. The check is echoed to the console.
+pub fn is_file(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    echo_operation("is_file", &path.display().to_string());
+    std::fs::metadata(path).is_ok_and(|m| m.is_file())
+}
+
+/// Returns `true` if the path exists and points to a directory.
+///
+/// Like the shell's `[ -d path ]`, symbolic links are followed and a missing path
+/// yields `false` instead of an error. The check is echoed to the console.
+pub fn is_dir(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    echo_operation("is_dir", &path.display().to_string());
+    std::fs::metadata(path).is_ok_and(|m| m.is_dir())
+}
+
+/// Returns `true` if the path exists and is a symbolic link.
+///
+/// Like the shell's `[ -L path ]`, the link itself is inspected (it may be dangling)
+/// and a missing path yields `false` instead of an error. The check is echoed to the console.
+pub fn is_symlink(path: impl AsRef<Path>) -> bool {
+    let path = path.as_ref();
+    echo_operation("is_symlink", &path.display().to_string());
+    std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+}
+
 /// Given a path, query the file system to get information about a file, directory, etc.
 ///
 /// This is a wrapper around [`std::fs::metadata`] that echoes the operation to the console.
@@ -72,6 +305,45 @@ pub fn metadata(path: impl AsRef<Path>) -> std::io::Result<std::fs::Metadata> {
     std::fs::metadata(path)
 }
 
+/// Format the Unix permission bits of a file like `ls -l` does, e.g. `rw-r--r--`.
+///
+/// The setuid, setgid and sticky bits are shown as `s` / `S` and `t` / `T` in
+/// place of the corresponding execute bit.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::fs;
+///
+/// let metadata = fs::metadata("Cargo.toml")?;
+/// println!("{}", fs::permissions_string(&metadata)); // rw-r--r--
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[cfg(unix)]
+pub fn permissions_string(metadata: &std::fs::Metadata) -> String {
+    use std::os::unix::fs::PermissionsExt;
+    format_mode(metadata.permissions().mode())
+}
+
+/// Format the permission bits of `mode` as in `rwxr-xr-x`.
+#[cfg(unix)]
+fn format_mode(mode: u32) -> String {
+    let mut s = String::with_capacity(9);
+    // (shift of the rwx triplet, special bit, its letter)
+    for (shift, special, letter) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = (mode >> shift) & 0o7;
+        s.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        s.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        s.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => letter,
+            (false, true) => letter.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    s
+}
+
 /// Read the entire contents of a file into a bytes vector.
 ///
 /// This is a wrapper around [`std::fs::read`] that echoes the operation to the console.
@@ -90,6 +362,15 @@ pub fn read_dir(path: impl AsRef<Path>) -> std::io::Result<std::fs::ReadDir> {
     std::fs::read_dir(path)
 }
 
+/// Read the target of a symbolic link.
+///
+/// This is a wrapper around [`std::fs::read_link`] that echoes the operation to the console.
+pub fn read_link(path: impl AsRef<Path>) -> std::io::Result<PathBuf> {
+    let path = path.as_ref();
+    echo_operation("read_link", &path.display().to_string());
+    std::fs::read_link(path)
+}
+
 /// Read the entire contents of a file into a string.
 ///
 /// This is a wrapper around [`std::fs::read_to_string`] that echoes the operation to the console.
@@ -105,14 +386,32 @@ pub fn read_to_string(path: impl AsRef<Path>) -> std::io::Result<String> {
 pub fn remove_dir(path: impl AsRef<Path>) -> std::io::Result<()> {
     let path = path.as_ref();
     echo_operation("remove_dir", &path.display().to_string());
+    if is_dry_run() {
+        return Ok(());
+    }
     std::fs::remove_dir(path)
 }
 
 /// Removes a directory at this path, after removing all its contents. Use carefully!
 ///
 /// This is a wrapper around [`std::fs::remove_dir_all`] that echoes the operation to the console.
+/// In dry-run mode the number of entries that would be removed is echoed as well.
 pub fn remove_dir_all(path: impl AsRef<Path>) -> std::io::Result<()> {
     let path = path.as_ref();
+    if is_dry_run() {
+        // Entries below `path` that can be listed, not counting `path` itself
+        let entries = WalkDir {
+            stack: Vec::new(),
+            pending: Some(path.to_path_buf()),
+        }
+        .filter(Result::is_ok)
+        .count();
+        echo_operation(
+            "remove_dir_all",
+            &format!("{} ({entries} entries)", path.display()),
+        );
+        return Ok(());
+    }
     echo_operation("remove_dir_all", &path.display().to_string());
     std::fs::remove_dir_all(path)
 }
@@ -123,9 +422,35 @@ pub fn remove_dir_all(path: impl AsRef<Path>) -> std::io::Result<()> {
 pub fn remove_file(path: impl AsRef<Path>) -> std::io::Result<()> {
     let path = path.as_ref();
     echo_operation("remove_file", &path.display().to_string());
+    if is_dry_run() {
+        return Ok(());
+    }
     std::fs::remove_file(path)
 }
 
+/// Remove many files, echoing a single summary line instead of one line per file.
+///
+/// Each path is removed with [`std::fs::remove_file`]. Returns the number of files
+/// removed. On failure, the error names the path that could not be removed and the
+/// remaining paths are not processed.
+pub fn remove_files<I, P>(paths: I) -> std::io::Result<usize>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let mut count = 0;
+    for path in paths {
+        let path = path.as_ref();
+        if !is_dry_run() {
+            std::fs::remove_file(path)
+                .map_err(|e| with_path_context(e, &path.display().to_string()))?;
+        }
+        count += 1;
+    }
+    echo_operation("remove_files", &format!("{count} files"));
+    Ok(count)
+}
+
 /// Rename a file or directory to a new name, replacing the original file if `to` already exists.
 ///
 /// This is a wrapper around [`std::fs::rename`] that echoes the operation to the console.
@@ -133,6 +458,9 @@ pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<(
     let from = from.as_ref();
     let to = to.as_ref();
     echo_operation("rename", &format!("{} -> {}", from.display(), to.display()));
+    if is_dry_run() {
+        return Ok(());
+    }
     std::fs::rename(from, to)
 }
 
@@ -141,10 +469,41 @@ pub fn rename(from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<(
 /// This is a wrapper around [`std::fs::set_permissions`] that echoes the operation to the console.
 pub fn set_permissions(path: impl AsRef<Path>, perm: std::fs::Permissions) -> std::io::Result<()> {
     let path = path.as_ref();
-    echo_operation("set_permissions", &path.display().to_string());
+    #[cfg(unix)]
+    let details = {
+        use std::os::unix::fs::PermissionsExt;
+        format!("{} {}", format_mode(perm.mode()), path.display())
+    };
+    #[cfg(not(unix))]
+    let details = if perm.readonly() {
+        format!("readonly {}", path.display())
+    } else {
+        format!("writable {}", path.display())
+    };
+    echo_operation("set_permissions", &details);
+    if is_dry_run() {
+        return Ok(());
+    }
     std::fs::set_permissions(path, perm)
 }
 
+/// Create a new symbolic link `link` pointing to `original`.
+///
+/// This is a wrapper around [`std::os::unix::fs::symlink`] that echoes the operation to the console.
+#[cfg(unix)]
+pub fn symlink(original: impl AsRef<Path>, link: impl AsRef<Path>) -> std::io::Result<()> {
+    let original = original.as_ref();
+    let link = link.as_ref();
+    echo_operation(
+        "symlink",
+        &format!("{} -> {}", original.display(), link.display()),
+    );
+    if is_dry_run() {
+        return Ok(());
+    }
+    std::os::unix::fs::symlink(original, link)
+}
+
 /// Query the metadata about a file without following symlinks.
 ///
 /// This is a wrapper around [`std::fs::symlink_metadata`] that echoes the operation to the console.
@@ -154,6 +513,137 @@ pub fn symlink_metadata(path: impl AsRef<Path>) -> std::io::Result<std::fs::Meta
     std::fs::symlink_metadata(path)
 }
 
+/// Run `f` with a backup of the file at `path`, restoring it if `f` fails.
+///
+/// The file is copied to `path.bak` before `f` runs. If `f` returns an error, the
+/// original is restored from the backup and the error is returned; otherwise the
+/// backup is removed. Both the backup and the restore are echoed to the console.
+///
+/// If `path.bak` already exists, it is left alone and an
+/// [`AlreadyExists`](std::io::ErrorKind::AlreadyExists) error is returned without
+/// running `f`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// fs::with_backup("config.toml", || {
+///     let config = fs::read_to_string("config.toml")?;
+///     fs::write("config.toml", config.replace("debug = true", "debug = false"))?;
+///     cmd!("app", "--check-config").run().map_err(std::io::Error::other)
+/// })?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn with_backup<T>(
+    path: impl AsRef<Path>,
+    f: impl FnOnce() -> std::io::Result<T>,
+) -> std::io::Result<T> {
+    let path = path.as_ref();
+    let mut backup = path.as_os_str().to_os_string();
+    backup.push(".bak");
+    let backup = std::path::PathBuf::from(backup);
+
+    echo_operation(
+        "backup",
+        &format!("{} -> {}", path.display(), backup.display()),
+    );
+    if is_dry_run() {
+        return f();
+    }
+    // Claim the backup path first so an existing backup is never overwritten
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&backup)
+        .map_err(|e| with_path_context(e, &backup.display().to_string()))?;
+    if let Err(e) = std::fs::copy(path, &backup) {
+        let _ = std::fs::remove_file(&backup);
+        return Err(with_path_context(e, &path.display().to_string()));
+    }
+
+    match f() {
+        Ok(value) => {
+            std::fs::remove_file(&backup)?;
+            Ok(value)
+        }
+        Err(err) => {
+            echo_operation(
+                "restore",
+                &format!("{} -> {}", backup.display(), path.display()),
+            );
+            std::fs::rename(&backup, path)
+                .map_err(|e| with_path_context(e, &backup.display().to_string()))?;
+            Err(err)
+        }
+    }
+}
+
+/// Recursively list all files and directories below `path`, depth first.
+///
+/// `path` itself is not yielded. Symbolic links are yielded but never followed,
+/// so links to directories cannot cause loops. Errors reading a directory are
+/// yielded in place of its entries and the walk continues. The walk is echoed
+/// once to the console when it starts.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// for path in fs::walk_dir("assets") {
+///     let path = path?;
+///     if path.extension().is_some_and(|ext| ext == "png") {
+///         cmd!("optipng", path).run()?;
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn walk_dir(path: impl AsRef<Path>) -> impl Iterator<Item = std::io::Result<PathBuf>> {
+    let path = path.as_ref();
+    echo_operation("walk_dir", &path.display().to_string());
+    WalkDir {
+        stack: Vec::new(),
+        pending: Some(path.to_path_buf()),
+    }
+}
+
+struct WalkDir {
+    stack: Vec<std::fs::ReadDir>,
+    // Directory to descend into before continuing with `stack`
+    pending: Option<PathBuf>,
+}
+
+impl Iterator for WalkDir {
+    type Item = std::io::Result<PathBuf>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(dir) = self.pending.take() {
+                match std::fs::read_dir(&dir) {
+                    Ok(entries) => self.stack.push(entries),
+                    Err(e) => return Some(Err(with_path_context(e, &dir.display().to_string()))),
+                }
+            }
+
+            match self.stack.last_mut()?.next() {
+                None => {
+                    self.stack.pop();
+                }
+                Some(Err(e)) => return Some(Err(e)),
+                Some(Ok(entry)) => {
+                    let path = entry.path();
+                    // `DirEntry::file_type` does not follow symbolic links
+                    if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                        self.pending = Some(path.clone());
+                    }
+                    return Some(Ok(path));
+                }
+            }
+        }
+    }
+}
+
 /// Write a slice as the entire contents of a file.
 ///
 /// This is a wrapper around [`std::fs::write`] that echoes the operation to the console.
@@ -164,5 +654,760 @@ pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Res
         "write",
         &format!("{} bytes -> {}", contents.len(), path.display()),
     );
+    if is_dry_run() {
+        return Ok(());
+    }
+    std::fs::write(path, contents)
+}
+
+/// Write a slice as the entire contents of a file, atomically replacing it.
+///
+/// The contents are written to a temporary file in the same directory, synced to
+/// disk, and then renamed over `path`, so readers see either the old or the new
+/// contents but never a partially written file, even if the process dies midway.
+/// The temporary file is removed if any step fails. If `path` already exists,
+/// its permissions are kept. Concurrent writers each get their own temporary
+/// file, so the last rename wins. The operation is echoed to the console.
+pub fn write_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    use std::io::Write;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    // Tells apart the temporary files of concurrent calls within this process
+    static TEMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    echo_operation(
+        "write_atomic",
+        &format!("{} bytes -> {}", contents.len(), path.display()),
+    );
+    if is_dry_run() {
+        return Ok(());
+    }
+
+    let file_name = path.file_name().ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{}: not a file path", path.display()),
+        )
+    })?;
+
+    // Never open an existing file, which may be another writer's or a planted
+    // symlink, but pick the next name instead
+    let (temp_path, file) = loop {
+        let mut temp_name = std::ffi::OsString::from(".");
+        temp_name.push(file_name);
+        temp_name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let temp_path = path.with_file_name(temp_name);
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+        {
+            Ok(file) => break (temp_path, file),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(with_path_context(e, &path.display().to_string())),
+        }
+    };
+
+    // Give the new file the mode of the one it replaces before writing to it,
+    // so a private file is never readable by others
+    let permissions = std::fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.permissions());
+    let write = |mut file: std::fs::File| {
+        if let Some(permissions) = permissions {
+            file.set_permissions(permissions)?;
+        }
+        file.write_all(contents)?;
+        file.sync_all()
+    };
+    let result = write(file).and_then(|()| std::fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result.map_err(|e| with_path_context(e, &path.display().to_string()))
+}
+
+/// Write a slice as the entire contents of a file and make it executable (mode `0755`).
+///
+/// This combines [`std::fs::write`] and [`std::fs::set_permissions`], echoing both
+/// operations to the console. Useful when generating scripts or hooks that must be
+/// runnable.
+#[cfg(unix)]
+pub fn write_executable(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = path.as_ref();
+    write(path, contents)?;
+    echo_operation(
+        "set_permissions",
+        &format!("{} {}", format_mode(0o755), path.display()),
+    );
+    if is_dry_run() {
+        return Ok(());
+    }
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+}
+
+/// Write a slice as the entire contents of a file, creating any missing parent directories first.
+///
+/// This combines [`std::fs::create_dir_all`] and [`std::fs::write`] and echoes a single
+/// operation to the console, noting when parent directories had to be created.
+pub fn write_new(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    let path = path.as_ref();
+    let contents = contents.as_ref();
+    let parent = path.parent().filter(|p| !p.as_os_str().is_empty());
+    let create_parents = parent.is_some_and(|p| !p.is_dir());
+
+    let details = format!("{} bytes -> {}", contents.len(), path.display());
+    if create_parents {
+        echo_operation("write_new", &format!("{details} (created parents)"));
+    } else {
+        echo_operation("write_new", &details);
+    }
+
+    if is_dry_run() {
+        return Ok(());
+    }
+    if let Some(parent) = parent.filter(|_| create_parents) {
+        std::fs::create_dir_all(parent)?;
+    }
     std::fs::write(path, contents)
 }
+
+/// Entry point for file system operations that are not echoed.
+///
+/// Each method performs the same operation as the function of the same name in
+/// this module, including in dry-run mode, but without echoing it. This lets
+/// libraries built on scriptify suppress noise selectively, where `NO_ECHO`
+/// would silence everything.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// fs::quiet().write("cache/state.json", "{}")?;
+/// let state = fs::quiet().read_to_string("cache/state.json")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn quiet() -> Quiet {
+    Quiet { _private: () }
+}
+
+/// File system operations that are not echoed, created by [`quiet`].
+#[derive(Debug, Clone, Copy)]
+pub struct Quiet {
+    _private: (),
+}
+
+impl Quiet {
+    /// [`append`] without echoing.
+    pub fn append(
+        &self,
+        path: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> std::io::Result<()> {
+        quietly(|| append(path, contents))
+    }
+
+    /// [`copy`] without echoing.
+    pub fn copy(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<u64> {
+        quietly(|| copy(from, to))
+    }
+
+    /// [`copy_files`] without echoing.
+    pub fn copy_files<I, P, Q>(&self, pairs: I) -> std::io::Result<usize>
+    where
+        I: IntoIterator<Item = (P, Q)>,
+        P: AsRef<Path>,
+        Q: AsRef<Path>,
+    {
+        quietly(|| copy_files(pairs))
+    }
+
+    /// [`create_dir`] without echoing.
+    pub fn create_dir(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        quietly(|| create_dir(path))
+    }
+
+    /// [`create_dir_all`] without echoing.
+    pub fn create_dir_all(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        quietly(|| create_dir_all(path))
+    }
+
+    /// [`glob`] without echoing.
+    pub fn glob(&self, pattern: &str) -> std::io::Result<Vec<PathBuf>> {
+        quietly(|| glob(pattern))
+    }
+
+    /// [`hard_link`] without echoing.
+    pub fn hard_link(
+        &self,
+        original: impl AsRef<Path>,
+        link: impl AsRef<Path>,
+    ) -> std::io::Result<()> {
+        quietly(|| hard_link(original, link))
+    }
+
+    /// [`is_file`] without echoing.
+    pub fn is_file(&self, path: impl AsRef<Path>) -> bool {
+        quietly(|| is_file(path))
+    }
+
+    /// [`is_dir`] without echoing.
+    pub fn is_dir(&self, path: impl AsRef<Path>) -> bool {
+        quietly(|| is_dir(path))
+    }
+
+    /// [`is_symlink`] without echoing.
+    pub fn is_symlink(&self, path: impl AsRef<Path>) -> bool {
+        quietly(|| is_symlink(path))
+    }
+
+    /// [`metadata`] without echoing.
+    pub fn metadata(&self, path: impl AsRef<Path>) -> std::io::Result<std::fs::Metadata> {
+        quietly(|| metadata(path))
+    }
+
+    /// [`read`] without echoing.
+    pub fn read(&self, path: impl AsRef<Path>) -> std::io::Result<Vec<u8>> {
+        quietly(|| read(path))
+    }
+
+    /// [`read_dir`] without echoing.
+    pub fn read_dir(&self, path: impl AsRef<Path>) -> std::io::Result<std::fs::ReadDir> {
+        quietly(|| read_dir(path))
+    }
+
+    /// [`read_link`] without echoing.
+    pub fn read_link(&self, path: impl AsRef<Path>) -> std::io::Result<PathBuf> {
+        quietly(|| read_link(path))
+    }
+
+    /// [`read_to_string`] without echoing.
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> std::io::Result<String> {
+        quietly(|| read_to_string(path))
+    }
+
+    /// [`remove_dir`] without echoing.
+    pub fn remove_dir(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        quietly(|| remove_dir(path))
+    }
+
+    /// [`remove_dir_all`] without echoing.
+    pub fn remove_dir_all(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        quietly(|| remove_dir_all(path))
+    }
+
+    /// [`remove_file`] without echoing.
+    pub fn remove_file(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        quietly(|| remove_file(path))
+    }
+
+    /// [`remove_files`] without echoing.
+    pub fn remove_files<I, P>(&self, paths: I) -> std::io::Result<usize>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        quietly(|| remove_files(paths))
+    }
+
+    /// [`rename`] without echoing.
+    pub fn rename(&self, from: impl AsRef<Path>, to: impl AsRef<Path>) -> std::io::Result<()> {
+        quietly(|| rename(from, to))
+    }
+
+    /// [`set_permissions`] without echoing.
+    pub fn set_permissions(
+        &self,
+        path: impl AsRef<Path>,
+        perm: std::fs::Permissions,
+    ) -> std::io::Result<()> {
+        quietly(|| set_permissions(path, perm))
+    }
+
+    /// [`symlink`] without echoing.
+    #[cfg(unix)]
+    pub fn symlink(
+        &self,
+        original: impl AsRef<Path>,
+        link: impl AsRef<Path>,
+    ) -> std::io::Result<()> {
+        quietly(|| symlink(original, link))
+    }
+
+    /// [`symlink_metadata`] without echoing.
+    pub fn symlink_metadata(&self, path: impl AsRef<Path>) -> std::io::Result<std::fs::Metadata> {
+        quietly(|| symlink_metadata(path))
+    }
+
+    /// [`walk_dir`] without echoing.
+    pub fn walk_dir(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> impl Iterator<Item = std::io::Result<PathBuf>> {
+        quietly(|| walk_dir(path))
+    }
+
+    /// [`write`] without echoing.
+    pub fn write(&self, path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+        quietly(|| write(path, contents))
+    }
+
+    /// [`write_atomic`] without echoing.
+    pub fn write_atomic(
+        &self,
+        path: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> std::io::Result<()> {
+        quietly(|| write_atomic(path, contents))
+    }
+
+    /// [`write_executable`] without echoing.
+    #[cfg(unix)]
+    pub fn write_executable(
+        &self,
+        path: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> std::io::Result<()> {
+        quietly(|| write_executable(path, contents))
+    }
+
+    /// [`write_new`] without echoing.
+    pub fn write_new(
+        &self,
+        path: impl AsRef<Path>,
+        contents: impl AsRef<[u8]>,
+    ) -> std::io::Result<()> {
+        quietly(|| write_new(path, contents))
+    }
+}
+
+/// Run `f` with echoing of file system operations suppressed on this thread.
+fn quietly<T>(f: impl FnOnce() -> T) -> T {
+    // Restores the previous state even if `f` panics
+    struct Restore(bool);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            QUIET.set(self.0);
+        }
+    }
+
+    let _restore = Restore(QUIET.replace(true));
+    f()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("scriptify_fs_{}_{}", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_path_predicates() {
+        let dir = temp_path("predicates");
+        let file = dir.join("file.txt");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&file, "data").unwrap();
+
+        assert!(is_file(&file));
+        assert!(!is_dir(&file));
+        assert!(is_dir(&dir));
+        assert!(!is_file(&dir));
+        assert!(!is_symlink(&file));
+
+        #[cfg(unix)]
+        {
+            let link = dir.join("link");
+            std::os::unix::fs::symlink(&file, &link).unwrap();
+            assert!(is_symlink(&link));
+            assert!(is_file(&link));
+
+            let dangling = dir.join("dangling");
+            std::os::unix::fs::symlink(dir.join("missing"), &dangling).unwrap();
+            assert!(is_symlink(&dangling));
+            assert!(!is_file(&dangling));
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_new_creates_parents() {
+        let dir = temp_path("write_new");
+        let _ = std::fs::remove_dir_all(&dir);
+        let file = dir.join("nested/deeper/file.txt");
+
+        write_new(&file, "first").unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "first");
+
+        // Existing parents are fine and the file is overwritten
+        write_new(&file, "second").unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "second");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_batch_copy_and_remove() {
+        let dir = temp_path("batch");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let sources: Vec<PathBuf> = (0..5).map(|i| dir.join(format!("src{i}.txt"))).collect();
+        for source in &sources {
+            std::fs::write(source, "abc").unwrap();
+        }
+        let targets: Vec<PathBuf> = (0..5).map(|i| dir.join(format!("dst{i}.txt"))).collect();
+
+        let copied = copy_files(sources.iter().zip(targets.iter())).unwrap();
+        assert_eq!(copied, 5);
+        assert!(targets.iter().all(|t| t.is_file()));
+
+        let removed = remove_files(&sources).unwrap();
+        assert_eq!(removed, 5);
+        assert!(sources.iter().all(|s| !s.exists()));
+
+        // The failing path is named in the error
+        let missing = dir.join("missing.txt");
+        let err = remove_files([&targets[0], &missing]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("missing.txt"));
+        assert!(!targets[0].exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_with_backup() {
+        let dir = temp_path("backup");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("config.toml");
+        let backup = dir.join("config.toml.bak");
+        std::fs::write(&file, "original").unwrap();
+
+        // Failure restores the original
+        let err = with_backup(&file, || {
+            std::fs::write(&file, "broken")?;
+            Err::<(), _>(std::io::Error::other("validation failed"))
+        })
+        .unwrap_err();
+        assert_eq!(err.to_string(), "validation failed");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "original");
+        assert!(!backup.exists());
+
+        // Success keeps the change and removes the backup
+        let value = with_backup(&file, || {
+            std::fs::write(&file, "updated")?;
+            Ok(42)
+        })
+        .unwrap();
+        assert_eq!(value, 42);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "updated");
+        assert!(!backup.exists());
+
+        // An existing backup is neither overwritten nor removed
+        std::fs::write(&backup, "user backup").unwrap();
+        let mut ran = false;
+        let err = with_backup(&file, || {
+            ran = true;
+            Ok(())
+        })
+        .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert!(!ran);
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "user backup");
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "updated");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match(b"*.rs", b"main.rs"));
+        assert!(wildcard_match(b"*.rs", b".rs"));
+        assert!(!wildcard_match(b"*.rs", b"main.rsx"));
+        assert!(wildcard_match(b"te?t*", b"test_file"));
+        assert!(wildcard_match("?.txt".as_bytes(), "é.txt".as_bytes()));
+        assert!(!wildcard_match(b"?", b""));
+    }
+
+    #[test]
+    fn test_glob() {
+        let dir = temp_path("glob");
+        let _ = std::fs::remove_dir_all(&dir);
+        for file in ["a.rs", "b.txt", ".hidden.rs", "sub/c.rs", "sub/deep/d.rs"] {
+            write_new(dir.join(file), "").unwrap();
+        }
+        let root = dir.display();
+
+        let found = glob(&format!("{root}/*.rs")).unwrap();
+        assert_eq!(found, vec![dir.join("a.rs")]);
+
+        let found = glob(&format!("{root}/**/*.rs")).unwrap();
+        assert_eq!(
+            found,
+            vec![
+                dir.join("a.rs"),
+                dir.join("sub/c.rs"),
+                dir.join("sub/deep/d.rs")
+            ]
+        );
+
+        let found = glob(&format!("{root}/su?/*")).unwrap();
+        assert_eq!(found, vec![dir.join("sub/c.rs"), dir.join("sub/deep")]);
+
+        assert!(glob(&format!("{root}/missing/*.rs")).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_executable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_path("write_executable");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("hook.sh");
+
+        write_executable(&script, "#!/bin/sh\necho hook ran\n").unwrap();
+        let mode = std::fs::metadata(&script).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+
+        let output = crate::cmd!(&script).no_echo().output().unwrap();
+        assert_eq!(output, "hook ran\n");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_permissions_string() {
+        use std::os::unix::fs::PermissionsExt;
+
+        assert_eq!(format_mode(0o644), "rw-r--r--");
+        assert_eq!(format_mode(0o755), "rwxr-xr-x");
+        assert_eq!(format_mode(0o100600), "rw-------");
+        assert_eq!(format_mode(0o4755), "rwsr-xr-x");
+        assert_eq!(format_mode(0o2644), "rw-r-Sr--");
+        assert_eq!(format_mode(0o1777), "rwxrwxrwt");
+
+        let dir = temp_path("permissions");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("file.txt");
+        std::fs::write(&file, "").unwrap();
+        std::fs::set_permissions(&file, std::fs::Permissions::from_mode(0o640)).unwrap();
+        let metadata = std::fs::metadata(&file).unwrap();
+        assert_eq!(permissions_string(&metadata), "rw-r-----");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_path_predicates_missing_path() {
+        let missing = temp_path("missing");
+        assert!(!is_file(&missing));
+        assert!(!is_dir(&missing));
+        assert!(!is_symlink(&missing));
+    }
+
+    #[test]
+    #[serial_test::serial]
+    fn test_quiet_operations() {
+        use crate::test_util::SharedBuf;
+
+        let dir = temp_path("quiet");
+        let file = dir.join("file.txt");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let buffer = SharedBuf::default();
+        crate::set_echo_writer(buffer.clone());
+        let fs = quiet();
+        fs.create_dir_all(&dir).unwrap();
+        fs.write(&file, "quiet data").unwrap();
+        assert!(fs.is_file(&file));
+        assert_eq!(fs.read_to_string(&file).unwrap(), "quiet data");
+        fs.remove_file(&file).unwrap();
+        assert!(!file.exists());
+
+        // Regular operations are still echoed afterwards
+        create_dir(dir.join("loud")).unwrap();
+        crate::reset_echo_writer();
+
+        // Other tests may echo into the buffer meanwhile, so only look at this one's lines
+        let echoed = buffer.contents();
+        let dir = dir.display().to_string();
+        let lines: Vec<&str> = echoed.lines().filter(|l| l.contains(&dir)).collect();
+        assert_eq!(lines.len(), 1, "{echoed}");
+        assert!(lines[0].contains("loud"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_quietly_restores_after_panic() {
+        let result = std::panic::catch_unwind(|| quietly(|| panic!("failed while quiet")));
+        assert!(result.is_err());
+        assert!(!QUIET.get());
+    }
+
+    #[test]
+    fn test_append() {
+        let file = temp_path("append.txt");
+        let _ = std::fs::remove_file(&file);
+
+        append(&file, "first\n").unwrap();
+        append(&file, b"second\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "first\nsecond\n");
+
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_and_read_link() {
+        let dir = temp_path("symlink");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("target.txt");
+        let link = dir.join("link.txt");
+        std::fs::write(&target, "data").unwrap();
+
+        symlink(&target, &link).unwrap();
+        assert_eq!(read_link(&link).unwrap(), target);
+        assert_eq!(std::fs::read_to_string(&link).unwrap(), "data");
+
+        // Not a link
+        assert!(read_link(&target).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_walk_dir() {
+        let dir = temp_path("walk_dir");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("a/b")).unwrap();
+        std::fs::create_dir_all(dir.join("c")).unwrap();
+        std::fs::write(dir.join("top.txt"), "").unwrap();
+        std::fs::write(dir.join("a/one.txt"), "").unwrap();
+        std::fs::write(dir.join("a/b/two.txt"), "").unwrap();
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&dir, dir.join("c/loop")).unwrap();
+
+        let mut paths: Vec<PathBuf> = walk_dir(&dir)
+            .map(|path| path.unwrap().strip_prefix(&dir).unwrap().to_path_buf())
+            .collect();
+        paths.sort();
+
+        let mut expected = vec!["a", "a/b", "a/b/two.txt", "a/one.txt", "c", "top.txt"];
+        if cfg!(unix) {
+            expected.insert(5, "c/loop");
+        }
+        let expected: Vec<PathBuf> = expected.into_iter().map(PathBuf::from).collect();
+        assert_eq!(paths, expected);
+
+        // A missing directory yields a single error
+        let results: Vec<_> = walk_dir(dir.join("missing")).collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let dir = temp_path("write_atomic");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("config.toml");
+
+        write_atomic(&file, "version = 1\n").unwrap();
+        write_atomic(&file, "version = 2\n").unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "version = 2\n");
+
+        // Failing to replace the target leaves no temporary file behind
+        std::fs::create_dir(dir.join("occupied")).unwrap();
+        std::fs::write(dir.join("occupied/file"), "").unwrap();
+        assert!(write_atomic(dir.join("occupied"), "data").is_err());
+
+        let mut names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["config.toml", "occupied"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_atomic_concurrent() {
+        let dir = temp_path("write_atomic_concurrent");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("shared.txt");
+
+        // Every writer replaces the file with its complete contents
+        let contents: Vec<String> = (0..8).map(|i| format!("{i}").repeat(100_000)).collect();
+        std::thread::scope(|scope| {
+            for data in &contents {
+                let file = &file;
+                scope.spawn(move || {
+                    for _ in 0..10 {
+                        quietly(|| write_atomic(file, data)).unwrap();
+                    }
+                });
+            }
+        });
+        let written = std::fs::read_to_string(&file).unwrap();
+        assert!(contents.contains(&written));
+
+        let names: Vec<_> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(names, ["shared.txt"]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_atomic_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = temp_path("write_atomic_mode");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (name, mode) in [("secret.txt", 0o600), ("script.sh", 0o755)] {
+            let file = dir.join(name);
+            std::fs::write(&file, "old").unwrap();
+            std::fs::set_permissions(&file, std::fs::Permissions::from_mode(mode)).unwrap();
+
+            write_atomic(&file, "new").unwrap();
+            assert_eq!(std::fs::read_to_string(&file).unwrap(), "new");
+            let actual = std::fs::metadata(&file).unwrap().permissions().mode() & 0o7777;
+            assert_eq!(actual, mode, "{name}");
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -32,8 +32,16 @@
 //! Currently supported platforms:
 //! - **Linux** ✅ Full support with native pipe optimization
 //! - **macOS** ✅ Full support with native pipe optimization
+//! - **Windows** ✅ Commands, pipelines and file operations
 //!
-//! Scriptify is designed for Unix-like systems and uses Unix shell commands and utilities.
+//! Scriptify is designed with Unix-like systems in mind, and most examples use Unix
+//! commands and utilities. Pipelines are built on the cross-platform `std::io::pipe`,
+//! so they work the same on Windows with its own programs (e.g. `cmd /C echo`).
+//! A few features are only available on Unix:
+//!
+//! - `Cmd::arg0`, `Cmd::new_process_group`, `Cmd::uid` and `Cmd::gid`
+//! - `fs::symlink` and `fs::write_executable`
+//! - Signal names in error messages for commands killed by a signal
 //!
 //! ## Requirements
 //!
@@ -152,6 +160,10 @@
 //!
 //! // Multiple arguments
 //! cmd!("echo", "Hello", "World").run()?;
+//!
+//! // Splat a collection of arguments with `..`
+//! let files = vec!["a.txt", "b.txt"];
+//! cmd!("cat", ..files).run()?;
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 //!
@@ -171,6 +183,29 @@
 //! println!("User: {} Age: {}", name, age);
 //! ```
 //!
+//! For progress messages that should follow the echo settings (`NO_ECHO` and
+//! [`set_echo_writer`]), use `echo!`, or `warn!` and `error!` for leveled
+//! diagnostics. All three write to stderr; `echo_out!` writes to stdout:
+//!
+//! ```no_run
+//! use scriptify::*;
+//!
+//! echo!("Building", "release");
+//! warn!("cache is stale");
+//! error!("tests failed:", 2);
+//! echo_out!("done");
+//! ```
+//!
+//! Any value can be colored for echoing with the [`StyleExt`] methods. Like the
+//! command echo, the colors are dropped when `NO_COLOR` is set:
+//!
+//! ```no_run
+//! use scriptify::*;
+//!
+//! echo!("Status:", "OK".green().bold());
+//! warn!("retrying in", 5.yellow(), "seconds");
+//! ```
+//!
 //! ### Builder Pattern
 //!
 //! Commands support a fluent builder pattern for complex configurations:
@@ -426,14 +461,10 @@
 //! let output = cmd!("whoami").no_echo().output()?;
 //! println!("Current user: {}", output.trim());
 //!
-//! // Global no echo mode using environment
-//! unsafe {
-//!     std::env::set_var("NO_ECHO", "1");
-//! }
+//! // Global verbosity level (NO_ECHO=1 in the environment also silences echo)
+//! set_verbosity(Verbosity::Silent);
 //! cmd!("echo", "This won't show the command").run()?;
-//! unsafe {
-//!     std::env::remove_var("NO_ECHO");
-//! }
+//! set_verbosity(Verbosity::Commands);
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 //!
@@ -449,12 +480,12 @@
 //! find_cmd.run()?;
 //!
 //! // Conditional arguments
-//! let mut cmd = cmd!("ls");
-//! cmd = cmd.arg("-l");
-//! if std::env::var("SHOW_ALL").is_ok() {
-//!     cmd = cmd.arg("-a");
-//! }
-//! cmd.run()?;
+//! let show_all = std::env::var("SHOW_ALL").is_ok();
+//! cmd!("ls")
+//!     .arg("-l")
+//!     .arg_if(show_all, "-a")
+//!     .arg_opt(std::env::var_os("LS_DIR"))
+//!     .run()?;
 //! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 //!
@@ -514,7 +545,7 @@
 //!
 //!     // Count total requests
 //!     let total = cmd!("wc", "-l")
-//!         .input(&fs::read_to_string("/var/log/nginx/access.log")?)
+//!         .input_file("/var/log/nginx/access.log")
 //!         .output()?;
 //!     println!("Total requests: {}", total.trim());
 //!
@@ -524,7 +555,7 @@
 //!         .pipe(cmd!("uniq", "-c"))
 //!         .pipe(cmd!("sort", "-nr"))
 //!         .pipe(cmd!("head", "-10"))
-//!         .input(&fs::read_to_string("/var/log/nginx/access.log")?)
+//!         .input_file("/var/log/nginx/access.log")
 //!         .output()?;
 //!
 //!     println!("Top 10 IPs:");
@@ -626,7 +657,11 @@
 //!
 //! You can control scriptify's behavior with environment variables:
 //!
-//! - `NO_ECHO`: Set to any value to suppress command echoing globally
+//! - `NO_ECHO`: Set to any value to suppress command echoing globally (see also [`set_verbosity`])
+//! - `SCRIPTIFY_DRY_RUN`: Set to `1` to echo commands and file changes without performing them
+//! - `SCRIPTIFY_TIMING`: Set to `1` to echo how long each command took
+//! - `SCRIPTIFY_ASSUME_YES`: Set to `1` to answer yes to every [`confirm`] prompt without asking
+//! - `NO_COLOR`: Set to any non-empty value to echo without colors (colors are also off when stderr is not a terminal)
 //!
 //! ```bash
 //! NO_ECHO=1 cargo run  # Run without command echoing
@@ -697,12 +732,34 @@ pub use std::path::{Path, PathBuf};
 mod cmd;
 pub use cmd::*;
 
+mod dry_run;
+pub use dry_run::{is_dry_run, set_dry_run};
+
+mod prompt;
+pub use prompt::confirm;
+
+mod timing;
+pub use timing::{is_timing, set_timing};
+
+mod verbosity;
+pub use verbosity::{Verbosity, set_verbosity, verbosity};
+
+mod which;
+pub use which::which;
+
 pub mod fs;
 
 mod output;
+#[doc(hidden)]
+pub use output::{EchoLevel, echo_values};
+pub use output::{reset_echo_writer, set_echo_writer};
 
 pub mod color;
+pub use color::{StyleExt, Styled, Theme, set_theme};
 mod style;
 
+#[cfg(test)]
+mod test_util;
+
 /// Result type with a boxed error for convenience
 pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
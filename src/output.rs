@@ -1,20 +1,165 @@
 //! Output utilities for scriptify
 
-/// Check if output should be echoed based on NO_ECHO environment variable
+use std::io::Write;
+use std::sync::{Mutex, MutexGuard};
+
+/// Check if output should be echoed based on the verbosity level, which
+/// defaults to the NO_ECHO environment variable
 pub(crate) fn should_echo() -> bool {
-    std::env::var_os("NO_ECHO").is_none()
+    crate::verbosity::verbosity() > crate::Verbosity::Silent
+}
+
+// Destination of echoed lines; `None` means stderr. The lock also serializes
+// writes so that lines from concurrent commands never interleave.
+static ECHO_WRITER: Mutex<Option<Box<dyn Write + Send>>> = Mutex::new(None);
+
+/// Send echoed commands and file operations to `writer` instead of stderr.
+///
+/// Each echoed line is written with a single `write_all` while holding a global
+/// lock, so lines from commands running concurrently never interleave. This is
+/// useful for capturing the trace in a log file or a buffer in tests.
+///
+/// With the `tracing` feature, echoed commands are instead emitted as
+/// `tracing` events at the `INFO` level with `program`, `args`, `cwd` and
+/// `command` fields, so they reach the application's subscriber.
+pub fn set_echo_writer(writer: impl Write + Send + 'static) {
+    *lock_echo_writer() = Some(Box::new(writer));
+}
+
+/// Send echoed lines to stderr again, dropping any writer set with [`set_echo_writer`].
+pub fn reset_echo_writer() {
+    *lock_echo_writer() = None;
 }
 
-/// Print to stderr if echo is enabled  
+fn lock_echo_writer() -> MutexGuard<'static, Option<Box<dyn Write + Send>>> {
+    ECHO_WRITER.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Print to stderr (or the echo writer) if echo is enabled, as one atomic line
 pub(crate) fn conditional_eprintln(args: std::fmt::Arguments) {
     if should_echo() {
-        eprintln!("{}", args);
+        let line = format!("{}\n", args);
+        let mut writer = lock_echo_writer();
+        let _ = match writer.as_mut() {
+            Some(writer) => writer.write_all(line.as_bytes()),
+            None => std::io::stderr().lock().write_all(line.as_bytes()),
+        };
+    }
+}
+
+/// Destination and level of a line printed by the `echo!` family of macros.
+#[doc(hidden)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EchoLevel {
+    /// Plain line on stderr (`echo!`)
+    Info,
+    /// Plain line on stdout (`echo_out!`)
+    Stdout,
+    /// Line on stderr tagged with `warn` (`warn!`)
+    Warn,
+    /// Line on stderr tagged with `error` (`error!`)
+    Error,
+}
+
+/// Print `values` separated by spaces at the given level, unless `NO_ECHO` is set.
+#[doc(hidden)]
+pub fn echo_values(level: EchoLevel, values: &[&dyn std::fmt::Display]) {
+    if !should_echo() {
+        return;
+    }
+
+    let mut line = String::new();
+    for (i, value) in values.iter().enumerate() {
+        if i > 0 {
+            line.push(' ');
+        }
+        line.push_str(&value.to_string());
+    }
+
+    let tag = |style: anstyle::Style, name: &str| {
+        let style = crate::color::echo_style(style);
+        format!("{style}{name}{style:#}")
+    };
+    match level {
+        EchoLevel::Info => conditional_eprintln(format_args!("{line}")),
+        EchoLevel::Stdout => {
+            let _ = writeln!(std::io::stdout().lock(), "{line}");
+        }
+        EchoLevel::Warn => conditional_eprintln(format_args!(
+            "{}: {line}",
+            tag(crate::style::BOLD_YELLOW, "warn")
+        )),
+        EchoLevel::Error => conditional_eprintln(format_args!(
+            "{}: {line}",
+            tag(crate::style::BOLD_RED, "error")
+        )),
     }
 }
 
+/// Print its arguments to stderr, separated by spaces.
+///
+/// Like echoed commands, the line goes to the writer set with
+/// [`set_echo_writer`](crate::set_echo_writer) if any, and is suppressed when
+/// `NO_ECHO` is set.
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// echo!("Value:", 42);
+/// ```
+#[macro_export]
+macro_rules! echo {
+    ($($value:expr),* $(,)?) => {
+        $crate::echo_values($crate::EchoLevel::Info, &[$(&$value as &dyn ::std::fmt::Display),*])
+    };
+}
+
+/// Like [`echo!`], but prints to stdout.
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// echo_out!("result:", 42);
+/// ```
+#[macro_export]
+macro_rules! echo_out {
+    ($($value:expr),* $(,)?) => {
+        $crate::echo_values($crate::EchoLevel::Stdout, &[$(&$value as &dyn ::std::fmt::Display),*])
+    };
+}
+
+/// Like [`echo!`], but prefixes the line with a yellow `warn:` tag.
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// warn!("config file not found, using defaults");
+/// ```
+#[macro_export]
+macro_rules! warn {
+    ($($value:expr),* $(,)?) => {
+        $crate::echo_values($crate::EchoLevel::Warn, &[$(&$value as &dyn ::std::fmt::Display),*])
+    };
+}
+
+/// Like [`echo!`], but prefixes the line with a red `error:` tag.
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// error!("build failed with", 3, "errors");
+/// ```
+#[macro_export]
+macro_rules! error {
+    ($($value:expr),* $(,)?) => {
+        $crate::echo_values($crate::EchoLevel::Error, &[$(&$value as &dyn ::std::fmt::Display),*])
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::SharedBuf;
     use serial_test::serial;
 
     #[test]
@@ -61,6 +206,42 @@ mod tests {
         }
     }
 
+    #[test]
+    #[serial]
+    fn test_echo_levels() {
+        let original = std::env::var("NO_ECHO").ok();
+        unsafe {
+            std::env::remove_var("NO_ECHO");
+        }
+        let buf = SharedBuf::default();
+        set_echo_writer(buf.clone());
+
+        crate::echo!("hello", 42);
+        crate::warn!("disk", "almost full");
+        crate::error!("failed");
+
+        unsafe {
+            std::env::set_var("NO_ECHO", "1");
+        }
+        crate::warn!("hidden");
+        crate::error!("hidden");
+
+        reset_echo_writer();
+        unsafe {
+            match original {
+                Some(val) => std::env::set_var("NO_ECHO", val),
+                None => std::env::remove_var("NO_ECHO"),
+            }
+        }
+
+        let output = buf.contents();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], "hello 42");
+        assert!(lines[1].contains("warn") && lines[1].ends_with(": disk almost full"));
+        assert!(lines[2].contains("error") && lines[2].ends_with(": failed"));
+    }
+
     #[test]
     fn test_conditional_functions_compile() {
         // Test that the functions compile and don't panic
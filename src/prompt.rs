@@ -0,0 +1,114 @@
+//! Interactive confirmation prompts.
+
+use std::io::{BufRead, Write};
+
+/// Ask a yes/no question on stderr and read the answer from stdin.
+///
+/// The prompt is shown as `message [y/N] `. Answers of `y` or `yes` in any
+/// case mean yes; anything else, including an empty line or the end of stdin,
+/// means no. The prompt is shown regardless of `NO_ECHO` and the verbosity
+/// level, since the user has to answer it.
+///
+/// If the `SCRIPTIFY_ASSUME_YES` environment variable is set to a non-empty
+/// value other than `0`, nothing is read and the answer is yes, for running
+/// scripts unattended.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// if confirm("Delete the build directory?")? {
+///     fs::remove_dir_all("build")?;
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn confirm(message: &str) -> std::io::Result<bool> {
+    let mut stderr = std::io::stderr().lock();
+    if assume_yes() {
+        write_prompt(&mut stderr, message)?;
+        writeln!(stderr, "y")?;
+        return Ok(true);
+    }
+    confirm_with(message, &mut std::io::stdin().lock(), &mut stderr)
+}
+
+fn assume_yes() -> bool {
+    std::env::var_os("SCRIPTIFY_ASSUME_YES").is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Show the prompt on `output` and read the answer from `input`.
+fn confirm_with(
+    message: &str,
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+) -> std::io::Result<bool> {
+    write_prompt(output, message)?;
+    output.flush()?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    Ok(is_yes(&answer))
+}
+
+fn write_prompt(output: &mut impl Write, message: &str) -> std::io::Result<()> {
+    let bold = crate::color::echo_style(crate::style::BOLD);
+    let prefix = crate::color::echo_style(crate::color::theme().prefix);
+    write!(output, "{bold}{message}{bold:#} {prefix}[y/N]{prefix:#} ")
+}
+
+fn is_yes(answer: &str) -> bool {
+    let answer = answer.trim();
+    answer.eq_ignore_ascii_case("y") || answer.eq_ignore_ascii_case("yes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    fn test_is_yes() {
+        for answer in ["y", "Y", "yes", "YES", " yes\n", "y\r\n"] {
+            assert!(is_yes(answer), "{answer:?}");
+        }
+        for answer in ["", "\n", "n", "no", "yep", "maybe"] {
+            assert!(!is_yes(answer), "{answer:?}");
+        }
+    }
+
+    #[test]
+    fn test_confirm_with() {
+        let mut output = Vec::new();
+        let answer = confirm_with("Continue?", &mut &b"yes\n"[..], &mut output).unwrap();
+        assert!(answer);
+        let prompt = String::from_utf8(output).unwrap();
+        assert_eq!(crate::color::strip(&prompt), "Continue? [y/N] ");
+
+        // An empty line and the end of input default to no
+        assert!(!confirm_with("Continue?", &mut &b"\n"[..], &mut Vec::new()).unwrap());
+        assert!(!confirm_with("Continue?", &mut &b""[..], &mut Vec::new()).unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn test_confirm_assume_yes() {
+        let original = std::env::var_os("SCRIPTIFY_ASSUME_YES");
+
+        unsafe {
+            std::env::set_var("SCRIPTIFY_ASSUME_YES", "1");
+        }
+        assert!(confirm("Proceed?").unwrap());
+
+        unsafe {
+            std::env::set_var("SCRIPTIFY_ASSUME_YES", "0");
+        }
+        assert!(!assume_yes());
+
+        unsafe {
+            match original {
+                Some(value) => std::env::set_var("SCRIPTIFY_ASSUME_YES", value),
+                None => std::env::remove_var("SCRIPTIFY_ASSUME_YES"),
+            }
+        }
+    }
+}
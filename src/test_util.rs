@@ -0,0 +1,26 @@
+//! Helpers shared by the unit tests.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+
+/// A writer that collects everything written to it, readable through any of
+/// its clones, e.g. after handing one to `set_echo_writer`.
+#[derive(Clone, Default)]
+pub(crate) struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuf {
+    /// Everything written so far.
+    pub(crate) fn contents(&self) -> String {
+        String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+    }
+}
+
+impl Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+//! Timing mode: echo how long each command took once it finishes.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// 0 = read `SCRIPTIFY_TIMING`, 1 = enabled, 2 = disabled
+static TIMING: AtomicU8 = AtomicU8::new(0);
+
+/// Enable or disable timing mode, overriding `SCRIPTIFY_TIMING`.
+///
+/// In timing mode every echoed command or pipeline that is run to completion
+/// is echoed a second time when it finishes, followed by the elapsed wall-clock
+/// time, e.g. `cmd cargo build (12.34s)`. Commands with echo disabled are not
+/// timed.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// set_timing(true);
+/// cmd!("cargo", "build", "--release").run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn set_timing(enabled: bool) {
+    TIMING.store(if enabled { 1 } else { 2 }, Ordering::Relaxed);
+}
+
+/// Returns `true` if timing mode is in effect.
+///
+/// This is the value set with [`set_timing`], or otherwise whether the
+/// `SCRIPTIFY_TIMING` environment variable is set to a non-empty value other
+/// than `0`.
+pub fn is_timing() -> bool {
+    match TIMING.load(Ordering::Relaxed) {
+        1 => true,
+        2 => false,
+        _ => std::env::var_os("SCRIPTIFY_TIMING").is_some_and(|v| !v.is_empty() && v != "0"),
+    }
+}
@@ -0,0 +1,59 @@
+//! Verbosity level: how much scriptify echoes.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// 0 = read `NO_ECHO`, otherwise the level plus one
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+/// How much scriptify echoes, see [`set_verbosity`].
+///
+/// Each level echoes everything the previous ones do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Verbosity {
+    /// Echo nothing, as when `NO_ECHO` is set.
+    Silent,
+    /// Echo commands, file operations and lines printed with [`echo!`](crate::echo)
+    /// and related macros. This is the default.
+    Commands,
+    /// Also echo the output captured from commands, e.g. by `output()`.
+    CommandsAndOutput,
+    /// Also echo how long each command took, as in timing mode.
+    Debug,
+}
+
+/// Set how much is echoed, overriding `NO_ECHO`.
+///
+/// Commands configured with `no_echo()` and operations run through
+/// [`fs::quiet`](crate::fs::quiet) stay silent at every level.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// set_verbosity(Verbosity::CommandsAndOutput);
+/// let branch = cmd!("git", "branch", "--show-current").output()?;
+///
+/// set_verbosity(Verbosity::Silent);
+/// cmd!("git", "fetch").run()?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn set_verbosity(level: Verbosity) {
+    VERBOSITY.store(level as u8 + 1, Ordering::Relaxed);
+}
+
+/// Returns the verbosity level in effect.
+///
+/// This is the level set with [`set_verbosity`], or otherwise
+/// [`Verbosity::Silent`] if the `NO_ECHO` environment variable is set and
+/// [`Verbosity::Commands`] if it is not.
+pub fn verbosity() -> Verbosity {
+    match VERBOSITY.load(Ordering::Relaxed) {
+        1 => Verbosity::Silent,
+        2 => Verbosity::Commands,
+        3 => Verbosity::CommandsAndOutput,
+        4 => Verbosity::Debug,
+        _ if std::env::var_os("NO_ECHO").is_some() => Verbosity::Silent,
+        _ => Verbosity::Commands,
+    }
+}
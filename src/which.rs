@@ -0,0 +1,112 @@
+//! Locate programs on `PATH`.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Find the executable that would be run for `program`, like the `which` command.
+///
+/// A bare name is searched for in each directory of `PATH` in order; on Unix
+/// only files with an executable bit set are considered, and on Windows the
+/// extensions listed in `PATHEXT` are tried as well. A name containing a path
+/// separator is checked as-is without consulting `PATH`. Returns `None` if no
+/// executable is found.
+///
+/// # Examples
+///
+/// ```no_run
+/// use scriptify::*;
+///
+/// if which("rg").is_some() {
+///     cmd!("rg", "TODO").run()?;
+/// } else {
+///     cmd!("grep", "-r", "TODO", ".").run()?;
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn which(program: impl AsRef<OsStr>) -> Option<PathBuf> {
+    which_in(program.as_ref(), std::env::var_os("PATH").as_deref())
+}
+
+/// Like [`which`], searching the given `PATH` value instead of this process's.
+pub(crate) fn which_in(program: &OsStr, path: Option<&OsStr>) -> Option<PathBuf> {
+    let program = Path::new(program);
+    if program.components().count() > 1 {
+        return find_executable(program);
+    }
+
+    std::env::split_paths(path?).find_map(|dir| find_executable(&dir.join(program)))
+}
+
+#[cfg(unix)]
+fn find_executable(path: &Path) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = std::fs::metadata(path).ok()?;
+    (metadata.is_file() && metadata.permissions().mode() & 0o111 != 0).then(|| path.to_path_buf())
+}
+
+#[cfg(not(unix))]
+fn find_executable(path: &Path) -> Option<PathBuf> {
+    if path.is_file() {
+        return Some(path.to_path_buf());
+    }
+    let extensions = std::env::var("PATHEXT").unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".into());
+    extensions
+        .split(';')
+        .filter(|ext| !ext.is_empty())
+        .map(|ext| {
+            let mut candidate = path.as_os_str().to_os_string();
+            candidate.push(ext);
+            PathBuf::from(candidate)
+        })
+        .find(|candidate| candidate.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_which_finds_sh() {
+        let path = which("sh").expect("sh should be on PATH");
+        assert!(path.is_absolute());
+        assert!(path.ends_with("sh"));
+
+        // A path is checked directly
+        assert_eq!(which(&path), Some(path));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_which_finds_cmd() {
+        let path = which("cmd").expect("cmd should be on PATH");
+        assert!(path.is_absolute());
+        assert!(path.is_file());
+    }
+
+    #[test]
+    fn test_which_missing() {
+        assert_eq!(which("scriptify-no-such-program-12345"), None);
+        assert_eq!(which("/nonexistent/dir/sh"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_which_requires_executable_bit() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(format!("scriptify_which_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let tool = dir.join("tool");
+        std::fs::write(&tool, "#!/bin/sh\n").unwrap();
+
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(which_in("tool".as_ref(), Some(dir.as_os_str())), None);
+
+        std::fs::set_permissions(&tool, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert_eq!(which_in("tool".as_ref(), Some(dir.as_os_str())), Some(tool));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
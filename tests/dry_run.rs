@@ -0,0 +1,84 @@
+//! Dry-run mode tests.
+//!
+//! Dry-run mode is process-wide, so these tests live in their own test binary
+//! where toggling it cannot affect commands run by other tests.
+#![cfg(unix)]
+
+mod common;
+
+use common::SharedBuf;
+use scriptify::*;
+
+/// Tests that commands and file changes are skipped in dry-run mode
+#[test]
+#[serial_test::serial]
+fn test_dry_run() {
+    let dir = std::env::temp_dir().join(format!("scriptify_dry_run_{}", std::process::id()));
+    let missing = dir.join("missing.txt");
+    let marker = dir.join("marker.txt");
+
+    set_dry_run(true);
+    assert!(is_dry_run());
+
+    fs::remove_file(&missing).unwrap();
+    fs::create_dir_all(&dir).unwrap();
+    assert!(!dir.exists());
+
+    cmd!("sh", "-c", "exit 1").run().unwrap();
+    let output = cmd!("echo", "hello").pipe(cmd!("cat")).output().unwrap();
+    assert!(output.is_empty());
+
+    set_dry_run(false);
+    assert!(!is_dry_run());
+
+    // Commands and file operations take effect again
+    fs::create_dir_all(&dir).unwrap();
+    assert!(fs::remove_file(&missing).is_err());
+    assert_eq!(cmd!("echo", "hello").output().unwrap(), "hello\n");
+
+    // Reading still works while writes are skipped
+    fs::write(&marker, "original").unwrap();
+    set_dry_run(true);
+    fs::write(&marker, "changed").unwrap();
+    cmd!("rm", &marker).run().unwrap();
+    assert_eq!(fs::read_to_string(&marker).unwrap(), "original");
+    set_dry_run(false);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Tests that dry-run copies and removals echo what they would affect
+#[test]
+#[serial_test::serial]
+fn test_dry_run_preview() {
+    let dir =
+        std::env::temp_dir().join(format!("scriptify_dry_run_preview_{}", std::process::id()));
+    let source = dir.join("source.txt");
+    let target = dir.join("target.txt");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    std::fs::write(&source, "12345").unwrap();
+    std::fs::write(dir.join("sub/nested.txt"), "x").unwrap();
+
+    let buffer = SharedBuf::default();
+    set_echo_writer(buffer.clone());
+    set_dry_run(true);
+    assert_eq!(fs::copy(&source, &target).unwrap(), 5);
+    assert_eq!(fs::copy_files([(&source, &target)]).unwrap(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+    set_dry_run(false);
+    reset_echo_writer();
+
+    let echoed = buffer.contents();
+    assert!(echoed.contains("target.txt (5 bytes)"), "{echoed}");
+    assert!(echoed.contains("1 files (5 bytes)"), "{echoed}");
+    // source.txt, sub and sub/nested.txt
+    assert!(echoed.contains("(3 entries)"), "{echoed}");
+
+    // Nothing was copied or removed
+    assert!(!target.exists());
+    assert_eq!(std::fs::read_to_string(&source).unwrap(), "12345");
+    assert!(dir.join("sub/nested.txt").exists());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
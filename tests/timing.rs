@@ -0,0 +1,43 @@
+//! Timing mode tests.
+//!
+//! Timing mode and the echo writer are process-wide, so these tests live in
+//! their own test binary where toggling them cannot affect other tests. With
+//! the `tracing` feature echoed commands become events, which are captured
+//! into the same buffer by a subscriber.
+#![cfg(unix)]
+
+mod common;
+
+use common::SharedBuf;
+use scriptify::*;
+
+/// Tests that finished commands are echoed with their elapsed time
+#[test]
+fn test_timing() {
+    let buf = SharedBuf::default();
+    set_echo_writer(buf.clone());
+    #[cfg(feature = "tracing")]
+    let _events = tracing::subscriber::set_default(buf.clone());
+
+    set_timing(true);
+    assert!(is_timing());
+    cmd!("sleep", "0.1").run().unwrap();
+    set_timing(false);
+    assert!(!is_timing());
+    cmd!("true").run().unwrap();
+
+    reset_echo_writer();
+
+    let output = buf.contents();
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 3, "{output}");
+    assert!(lines[0].contains("sleep"));
+    assert!(lines[1].contains("sleep"));
+    let seconds = lines[1]
+        .rsplit_once('(')
+        .and_then(|(_, rest)| rest.split_once("s)"))
+        .map(|(secs, _)| secs)
+        .unwrap();
+    assert!(seconds.parse::<f64>().unwrap() >= 0.1);
+    assert!(lines[2].contains("true") && !lines[2].contains("s)"));
+}
@@ -0,0 +1,67 @@
+//! Verbosity level tests.
+//!
+//! The verbosity level and the echo writer are process-wide, so these tests
+//! live in their own test binary where changing them cannot affect other tests.
+//! With the `tracing` feature echoed commands become events, which are
+//! captured into the same buffer by a subscriber.
+#![cfg(unix)]
+
+mod common;
+
+use common::SharedBuf;
+use scriptify::*;
+
+/// Run a command, a file operation and `echo!` at `level` and return what was echoed.
+fn echoed_at(level: Verbosity) -> String {
+    let buf = SharedBuf::default();
+    set_echo_writer(buf.clone());
+    #[cfg(feature = "tracing")]
+    let _events = tracing::subscriber::set_default(buf.clone());
+    set_verbosity(level);
+    assert_eq!(verbosity(), level);
+
+    let output = cmd!("echo", "captured-output").output().unwrap();
+    assert_eq!(output, "captured-output\n");
+    let _ = fs::metadata("/");
+    echo!("message");
+
+    set_verbosity(Verbosity::Commands);
+    reset_echo_writer();
+    buf.contents()
+}
+
+/// Tests which categories of messages each verbosity level echoes
+#[test]
+fn test_verbosity_levels() {
+    let silent = echoed_at(Verbosity::Silent);
+    assert!(silent.is_empty(), "{silent}");
+
+    let commands = echoed_at(Verbosity::Commands);
+    let lines: Vec<&str> = commands.lines().collect();
+    assert_eq!(lines.len(), 3, "{commands}");
+    assert!(lines[0].contains("echo captured-output"));
+    assert!(lines[1].contains("metadata"));
+    assert_eq!(lines[2], "message");
+
+    let with_output = echoed_at(Verbosity::CommandsAndOutput);
+    let lines: Vec<&str> = with_output.lines().collect();
+    assert_eq!(lines.len(), 4, "{with_output}");
+    assert_eq!(lines[1], "captured-output");
+
+    let debug = echoed_at(Verbosity::Debug);
+    let lines: Vec<&str> = debug.lines().collect();
+    assert_eq!(lines.len(), 5, "{debug}");
+    assert!(lines[1].contains("echo captured-output") && lines[1].ends_with("s)"));
+    assert_eq!(lines[2], "captured-output");
+
+    // Commands with echo disabled stay silent at every level
+    let buf = SharedBuf::default();
+    set_echo_writer(buf.clone());
+    #[cfg(feature = "tracing")]
+    let _events = tracing::subscriber::set_default(buf.clone());
+    set_verbosity(Verbosity::Debug);
+    cmd!("echo", "hidden").no_echo().output().unwrap();
+    set_verbosity(Verbosity::Commands);
+    reset_echo_writer();
+    assert!(buf.contents().is_empty());
+}
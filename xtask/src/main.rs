@@ -86,6 +86,10 @@ fn run_precommit(verbose: bool) -> Result<()> {
         println!("🧪 Running tests...");
     }
     cmd!("cargo", "test").current_dir(&project_root).run()?;
+    // Feature-gated code such as `tracing` and `encoding` is only tested here
+    cmd!("cargo", "test", "--all-features")
+        .current_dir(&project_root)
+        .run()?;
     if !verbose {
         println!("✅ Tests passed!");
     }
@@ -176,6 +180,10 @@ fn run_ci(verbose: bool) -> Result<()> {
         println!("🧪 Running tests...");
     }
     cmd!("cargo", "test").current_dir(&project_root).run()?;
+    // Feature-gated code such as `tracing` and `encoding` is only tested here
+    cmd!("cargo", "test", "--all-features")
+        .current_dir(&project_root)
+        .run()?;
     if !verbose {
         println!("✅ Tests passed!");
     }